@@ -1,6 +1,7 @@
 use std::{
     env,
     error::Error,
+    fs,
     path::{Path, PathBuf},
 };
 use std::process::Command;
@@ -37,9 +38,9 @@ fn init_submodule(uacpi_path: &Path) {
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let project_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
-    let uacpi_path = Path::new(&project_dir).join("vendor");
+/// Compiles the vendored uACPI sources and returns the include directory.
+fn build_vendored(project_dir: &Path) -> String {
+    let uacpi_path = project_dir.join("vendor");
 
     init_submodule(&uacpi_path);
 
@@ -69,11 +70,42 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     cc.compile("uacpi");
 
+    format!("{uacpi_path_str}/include")
+}
+
+/// Links against a prebuilt libuacpi and returns the include directory.
+/// UACPI_INCLUDE_DIR must point to the directory containing the uacpi/ headers,
+/// UACPI_LIB_DIR may point to the directory containing the library if it is
+/// not in the default linker search path.
+/// The library must be built with the same UACPI_SIZED_FREES and
+/// UACPI_REDUCED_HARDWARE configuration as this crate.
+fn link_system() -> String {
+    println!("cargo:rerun-if-env-changed=UACPI_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=UACPI_LIB_DIR");
+
+    if let Some(lib_dir) = env::var_os("UACPI_LIB_DIR") {
+        println!("cargo:rustc-link-search=native={}", lib_dir.to_str().unwrap());
+    }
+    println!("cargo:rustc-link-lib=uacpi");
+
+    env::var("UACPI_INCLUDE_DIR")
+        .expect("the system-uacpi feature requires UACPI_INCLUDE_DIR to point to the uACPI headers")
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let project_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
+
+    let include_dir = if cfg!(feature = "system-uacpi") {
+        link_system()
+    } else {
+        build_vendored(Path::new(&project_dir))
+    };
+
     let bindings = bindgen::Builder::default()
         .header("wrapper.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .clang_args(&[
-            "-Ivendor/include",
+            &format!("-I{include_dir}"),
             "-DUACPI_SIZED_FREES=1",
             #[cfg(feature = "reduced-hardware")]
             "-DUACPI_REDUCED_HARDWARE=1",
@@ -89,5 +121,30 @@ fn main() -> Result<(), Box<dyn Error>> {
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
 
+    emit_bindings(&out_path.join("bindings.rs"));
+
     Ok(())
 }
+
+/// Copies the generated bindings to UACPI_EMIT_BINDINGS, if set, for inspection,
+/// e.g. when a struct layout doesn't match the uACPI version in use.
+/// A relative path is taken relative to the uacpi-sys directory.
+fn emit_bindings(bindings: &Path) {
+    println!("cargo:rerun-if-env-changed=UACPI_EMIT_BINDINGS");
+
+    let Some(dest) = env::var_os("UACPI_EMIT_BINDINGS") else {
+        return;
+    };
+    let dest = Path::new(&env::var_os("CARGO_MANIFEST_DIR").unwrap()).join(dest);
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).expect("Couldn't create the directory for the emitted bindings");
+    }
+    fs::copy(bindings, &dest).expect("Couldn't copy the bindings to UACPI_EMIT_BINDINGS");
+
+    // bindgen already formats its output if rustfmt is installed, this only catches
+    // the case where it isn't found through the path bindgen uses
+    let _ = Command::new("rustfmt").arg(&dest).status();
+
+    println!("cargo:warning=uacpi-sys bindings written to {}", dest.display());
+}
@@ -5,3 +5,6 @@
 #include <uacpi/event.h>
 #include <uacpi/resources.h>
 #include <uacpi/utilities.h>
+#include <uacpi/opregion.h>
+#include <uacpi/context.h>
+#include <uacpi/osi.h>
@@ -0,0 +1,187 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ffi::CStr;
+use crate::{collect_devices, eval, eval_sta, NamespaceNode, Object, Status, STA_BATTERY_PRESENT};
+
+/// The PNP id of control method batteries.
+pub const BATTERY_HID: &CStr = c"PNP0C0A";
+
+/// The unit of the capacities and rates reported by a battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUnit {
+    /// Capacities are in mWh and rates in mW.
+    MilliWatt,
+    /// Capacities are in mAh and rates in mA.
+    MilliAmp,
+}
+
+/// The static information of a battery, decoded from _BIX or _BIF.
+/// Values the firmware reports as unknown are None.
+#[derive(Debug, Clone)]
+pub struct BatteryInfo {
+    pub power_unit: PowerUnit,
+    pub design_capacity: Option<u32>,
+    pub last_full_capacity: Option<u32>,
+    pub rechargeable: bool,
+    /// The nominal voltage in mV.
+    pub design_voltage: Option<u32>,
+    pub design_capacity_warning: u32,
+    pub design_capacity_low: u32,
+    /// Only reported by _BIX.
+    pub cycle_count: Option<u32>,
+    pub granularity_low_to_warning: u32,
+    pub granularity_warning_to_full: u32,
+    pub model_number: String,
+    pub serial_number: String,
+    pub battery_type: String,
+    pub oem_info: String,
+}
+
+/// The dynamic state of a battery, decoded from _BST.
+/// Values the firmware reports as unknown are None.
+#[derive(Debug, Clone, Copy)]
+pub struct BatteryStatus {
+    pub state: u32,
+    pub present_rate: Option<u32>,
+    pub remaining_capacity: Option<u32>,
+    /// The voltage in mV.
+    pub present_voltage: Option<u32>,
+}
+
+impl BatteryStatus {
+    pub fn discharging(&self) -> bool {
+        self.state & (1 << 0) != 0
+    }
+
+    pub fn charging(&self) -> bool {
+        self.state & (1 << 1) != 0
+    }
+
+    /// The battery is critically low.
+    pub fn critical(&self) -> bool {
+        self.state & (1 << 2) != 0
+    }
+
+    /// Charging is limited, e.g. to preserve battery health.
+    pub fn charge_limiting(&self) -> bool {
+        self.state & (1 << 3) != 0
+    }
+}
+
+/// A control method battery, i.e. a PNP0C0A device.
+#[derive(Debug)]
+pub struct Battery(NamespaceNode);
+
+impl Battery {
+    pub fn new(node: NamespaceNode) -> Self {
+        Self(node)
+    }
+
+    /// Collects all the batteries in the namespace, including those whose slot is empty.
+    pub fn find_all() -> Result<Vec<Battery>, Status> {
+        collect_devices(&[BATTERY_HID]).map(|nodes| nodes.into_iter().map(Battery).collect())
+    }
+
+    pub fn node(&self) -> &NamespaceNode {
+        &self.0
+    }
+
+    /// Checks via _STA whether a battery is inserted.
+    pub fn present(&self) -> Result<bool, Status> {
+        eval_sta(&self.0).map(|sta| sta & STA_BATTERY_PRESENT != 0)
+    }
+
+    /// Evaluates _BIX, falling back to _BIF on older firmware.
+    pub fn info(&self) -> Result<BatteryInfo, Status> {
+        match eval(&self.0, c"_BIX", []) {
+            Ok(bix) => decode_bix(&package_fields(&bix, 20)?),
+            Err(Status::NotFound) => decode_bif(&package_fields(&eval(&self.0, c"_BIF", [])?, 13)?),
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Evaluates _BST.
+    pub fn status(&self) -> Result<BatteryStatus, Status> {
+        let fields = package_fields(&eval(&self.0, c"_BST", [])?, 4)?;
+        Ok(BatteryStatus {
+            state: int(&fields[0])?,
+            present_rate: known(int(&fields[1])?),
+            remaining_capacity: known(int(&fields[2])?),
+            present_voltage: known(int(&fields[3])?),
+        })
+    }
+}
+
+/// Returns the elements of a package with at least `len` elements.
+fn package_fields(obj: &Object, len: usize) -> Result<Vec<Object>, Status> {
+    let fields: Vec<_> = obj.get_package().ok_or(Status::TypeMismatch)?.collect();
+    if fields.len() < len {
+        return Err(Status::TypeMismatch);
+    }
+    Ok(fields)
+}
+
+fn int(obj: &Object) -> Result<u32, Status> {
+    obj.get_int().map(|value| value as u32).ok_or(Status::TypeMismatch)
+}
+
+/// Maps the "unknown" value used by the battery methods to None.
+fn known(value: u32) -> Option<u32> {
+    (value != 0xFFFFFFFF).then_some(value)
+}
+
+/// Battery strings are sometimes returned as buffers, both are accepted.
+fn string(obj: &Object) -> String {
+    let bytes = match (obj.get_string(), obj.get_buffer()) {
+        (Some(str), _) => str.to_bytes(),
+        (None, Some(buffer)) => buffer.split(|byte| *byte == 0).next().unwrap_or(&[]),
+        (None, None) => &[],
+    };
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn power_unit(obj: &Object) -> Result<PowerUnit, Status> {
+    match int(obj)? {
+        0 => Ok(PowerUnit::MilliWatt),
+        _ => Ok(PowerUnit::MilliAmp),
+    }
+}
+
+fn decode_bif(fields: &[Object]) -> Result<BatteryInfo, Status> {
+    Ok(BatteryInfo {
+        power_unit: power_unit(&fields[0])?,
+        design_capacity: known(int(&fields[1])?),
+        last_full_capacity: known(int(&fields[2])?),
+        rechargeable: int(&fields[3])? == 1,
+        design_voltage: known(int(&fields[4])?),
+        design_capacity_warning: int(&fields[5])?,
+        design_capacity_low: int(&fields[6])?,
+        cycle_count: None,
+        granularity_low_to_warning: int(&fields[7])?,
+        granularity_warning_to_full: int(&fields[8])?,
+        model_number: string(&fields[9]),
+        serial_number: string(&fields[10]),
+        battery_type: string(&fields[11]),
+        oem_info: string(&fields[12]),
+    })
+}
+
+/// Decodes revision 0 and 1 of _BIX, which only differ by a trailing field.
+fn decode_bix(fields: &[Object]) -> Result<BatteryInfo, Status> {
+    Ok(BatteryInfo {
+        power_unit: power_unit(&fields[1])?,
+        design_capacity: known(int(&fields[2])?),
+        last_full_capacity: known(int(&fields[3])?),
+        rechargeable: int(&fields[4])? == 1,
+        design_voltage: known(int(&fields[5])?),
+        design_capacity_warning: int(&fields[6])?,
+        design_capacity_low: int(&fields[7])?,
+        cycle_count: known(int(&fields[8])?),
+        granularity_low_to_warning: int(&fields[14])?,
+        granularity_warning_to_full: int(&fields[15])?,
+        model_number: string(&fields[16]),
+        serial_number: string(&fields[17]),
+        battery_type: string(&fields[18]),
+        oem_info: string(&fields[19]),
+    })
+}
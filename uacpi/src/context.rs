@@ -0,0 +1,25 @@
+use crate::LogLevel;
+
+/// Sets the log level uACPI uses for its messages.
+/// Unlike the level passed to init this can be changed at any time.
+pub fn set_log_level(log_level: LogLevel) {
+    unsafe { uacpi_sys::uacpi_context_set_log_level(log_level.0) }
+}
+
+/// Sets the number of seconds a single AML While loop may run
+/// before it is aborted with AmlLoopTimeout.
+/// Call this before namespace_load for the limit to also apply to table load.
+pub fn set_loop_timeout(seconds: u32) {
+    unsafe { uacpi_sys::uacpi_context_set_loop_timeout(seconds) }
+}
+
+/// Returns the current AML loop timeout in seconds.
+pub fn get_loop_timeout() -> u32 {
+    unsafe { uacpi_sys::uacpi_context_get_loop_timeout() }
+}
+
+/// Sets the maximum depth of nested AML method calls.
+/// Call this before namespace_load for the limit to also apply to table load.
+pub fn set_max_call_stack_depth(depth: u32) {
+    unsafe { uacpi_sys::uacpi_context_set_max_call_stack_depth(depth) }
+}
@@ -0,0 +1,639 @@
+use alloc::boxed::Box;
+use core::any::Any;
+use core::ffi::c_void;
+use core::fmt::{self, Display};
+#[cfg(not(feature = "reduced-hardware"))]
+use crate::require_full_hardware;
+use crate::{
+    madt_entries, table_fadt, table_find_by_signature, MadtEntry, NamespaceNode, Status,
+    MADT_SIGNATURE,
+};
+
+/// How a GPE is triggered.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpeTriggering {
+    Level = uacpi_sys::UACPI_GPE_TRIGGERING_LEVEL,
+    Edge = uacpi_sys::UACPI_GPE_TRIGGERING_EDGE,
+}
+
+/// The value passed to Notify, values below 0x80 have a meaning defined by the ACPI specification,
+/// values from 0x80 up are specific to the device type.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NotifyValue(pub u64);
+
+impl NotifyValue {
+    /// Re-enumerate the devices at and below the notified node.
+    pub const BUS_CHECK: Self = Self(0x00);
+    /// Re-enumerate the notified device only, e.g. it was inserted or removed.
+    pub const DEVICE_CHECK: Self = Self(0x01);
+    /// The device woke the system.
+    pub const DEVICE_WAKE: Self = Self(0x02);
+    /// The device should be ejected, the OS is expected to run _EJx.
+    pub const EJECT_REQUEST: Self = Self(0x03);
+    /// A device check on a device that may not be lightly re-enumerated, e.g. a dock.
+    pub const DEVICE_CHECK_LIGHT: Self = Self(0x04);
+    /// The frequency of the device bus changed.
+    pub const FREQUENCY_MISMATCH: Self = Self(0x05);
+    /// The device bus mode changed.
+    pub const BUS_MODE_MISMATCH: Self = Self(0x06);
+    /// The device was powered off because of a fault.
+    pub const POWER_FAULT: Self = Self(0x07);
+    /// The capabilities of the device changed, e.g. its _OSC.
+    pub const CAPABILITIES_CHECK: Self = Self(0x08);
+    /// The _PLD of the device changed.
+    pub const DEVICE_PLD_CHECK: Self = Self(0x09);
+    /// The system locality information changed.
+    pub const SYSTEM_LOCALITY_UPDATE: Self = Self(0x0B);
+    /// The system is about to be shut down.
+    pub const SHUTDOWN_REQUEST: Self = Self(0x0C);
+    /// The system affinity information changed.
+    pub const SYSTEM_RESOURCE_AFFINITY_UPDATE: Self = Self(0x0D);
+    /// The _HMA of the device changed.
+    pub const HETEROGENEOUS_MEMORY_ATTRIBUTES_UPDATE: Self = Self(0x0E);
+    /// The device was disconnected because of an error and should be recovered,
+    /// e.g. by PCIe downstream port containment.
+    pub const ERROR_DISCONNECT_RECOVER: Self = Self(0x0F);
+
+    /// The value `value` from the device-specific range, i.e. 0x80 + `value`.
+    pub const fn device_specific(value: u8) -> Self {
+        Self(0x80 + value as u64)
+    }
+
+    /// Returns the offset into the device-specific range, None for ACPI-defined values.
+    pub fn as_device_specific(&self) -> Option<u8> {
+        self.0.checked_sub(0x80).and_then(|value| u8::try_from(value).ok())
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(match *self {
+            Self::BUS_CHECK => "BusCheck",
+            Self::DEVICE_CHECK => "DeviceCheck",
+            Self::DEVICE_WAKE => "DeviceWake",
+            Self::EJECT_REQUEST => "EjectRequest",
+            Self::DEVICE_CHECK_LIGHT => "DeviceCheckLight",
+            Self::FREQUENCY_MISMATCH => "FrequencyMismatch",
+            Self::BUS_MODE_MISMATCH => "BusModeMismatch",
+            Self::POWER_FAULT => "PowerFault",
+            Self::CAPABILITIES_CHECK => "CapabilitiesCheck",
+            Self::DEVICE_PLD_CHECK => "DevicePldCheck",
+            Self::SYSTEM_LOCALITY_UPDATE => "SystemLocalityUpdate",
+            Self::SHUTDOWN_REQUEST => "ShutdownRequest",
+            Self::SYSTEM_RESOURCE_AFFINITY_UPDATE => "SystemResourceAffinityUpdate",
+            Self::HETEROGENEOUS_MEMORY_ATTRIBUTES_UPDATE => "HeterogeneousMemoryAttributesUpdate",
+            Self::ERROR_DISCONNECT_RECOVER => "ErrorDisconnectRecover",
+            _ => return None,
+        })
+    }
+}
+
+impl From<u64> for NotifyValue {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for NotifyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.name() {
+            f.write_str(name)
+        } else if self.as_device_specific().is_some() {
+            write!(f, "DeviceSpecific(0x{:02X})", self.0)
+        } else {
+            write!(f, "Reserved(0x{:X})", self.0)
+        }
+    }
+}
+
+/// An event queued by a handler installed with install_notify_sink or install_gpe_sink.
+#[derive(Debug)]
+pub enum Event {
+    /// AML executed Notify(`node`, `value`).
+    Notify(NamespaceNode, NotifyValue),
+    /// The GPE at `index` of the GPE block device `node` fired.
+    /// The GPE stays disabled until finish_gpe is called for it.
+    Gpe(NamespaceNode, u16),
+}
+
+/// A channel-like queue that events are pushed into.
+/// push may be called from interrupt context and must not block,
+/// the events are meant to be consumed later, e.g. by an async task.
+pub trait EventSink {
+    fn push(&self, event: Event);
+}
+
+impl<F: Fn(Event)> EventSink for F {
+    fn push(&self, event: Event) {
+        self(event)
+    }
+}
+
+enum InstalledHandler {
+    Notify(uacpi_sys::uacpi_notify_handler),
+    #[cfg(not(feature = "reduced-hardware"))]
+    Gpe(u16, uacpi_sys::uacpi_gpe_handler),
+}
+
+/// An installed notify or GPE handler, uninstalled on drop.
+pub struct EventHandler {
+    node: *mut uacpi_sys::uacpi_namespace_node,
+    handler: InstalledHandler,
+    _context: Box<dyn Any>,
+}
+
+impl Drop for EventHandler {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = match self.handler {
+                InstalledHandler::Notify(handler) => {
+                    uacpi_sys::uacpi_uninstall_notify_handler(self.node, handler)
+                }
+                #[cfg(not(feature = "reduced-hardware"))]
+                InstalledHandler::Gpe(index, handler) => {
+                    uacpi_sys::uacpi_uninstall_gpe_handler(self.node, index, handler)
+                }
+            };
+        }
+    }
+}
+
+unsafe extern "C" fn notify_sink_cb<S: EventSink>(
+    context: uacpi_sys::uacpi_handle,
+    node: *mut uacpi_sys::uacpi_namespace_node,
+    value: uacpi_sys::uacpi_u64
+) -> uacpi_sys::uacpi_status {
+    let sink = &*(context as *const S);
+    crate::kernel_api::no_unwind(|| sink.push(Event::Notify(NamespaceNode(node), NotifyValue(value))));
+    Status::Ok as _
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+unsafe extern "C" fn gpe_sink_cb<S: EventSink>(
+    context: uacpi_sys::uacpi_handle,
+    gpe_device: *mut uacpi_sys::uacpi_namespace_node,
+    index: uacpi_sys::uacpi_u16
+) -> uacpi_sys::uacpi_interrupt_ret {
+    let sink = &*(context as *const S);
+    crate::kernel_api::no_unwind(|| sink.push(Event::Gpe(NamespaceNode(gpe_device), index)));
+    // not re-enabled here, the consumer calls finish_gpe once it's done
+    uacpi_sys::UACPI_INTERRUPT_HANDLED
+}
+
+/// Installs a notify handler on `node` that pushes every notification into `sink`.
+/// Passing the root node receives notifications for all nodes.
+/// The handler stays installed until the returned guard is dropped.
+/// uACPI delivers notifications from its notify work, which may run on any thread.
+pub fn install_notify_sink<S: EventSink + Send + Sync + 'static>(
+    node: &NamespaceNode,
+    sink: S
+) -> Result<EventHandler, Status> {
+    let node = node.raw();
+    let sink = Box::new(sink);
+    let handler: uacpi_sys::uacpi_notify_handler = Some(notify_sink_cb::<S>);
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_install_notify_handler(
+            node,
+            handler,
+            &*sink as *const S as *mut c_void
+        ).into()
+    };
+
+    match status {
+        Status::Ok => Ok(EventHandler {
+            node,
+            handler: InstalledHandler::Notify(handler),
+            _context: sink,
+        }),
+        _ => Err(status)
+    }
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Installs a handler for the GPE at `index` of `gpe_device` that pushes every occurrence into `sink`.
+/// `gpe_device` is the GPE block device, or the root node for the FADT GPE blocks.
+/// The GPE is left disabled after it fires, call finish_gpe once the event has been consumed.
+/// The handler stays installed until the returned guard is dropped.
+/// uACPI calls the handler from the SCI handler, which may run on any CPU.
+pub fn install_gpe_sink<S: EventSink + Send + Sync + 'static>(
+    gpe_device: &NamespaceNode,
+    index: u16,
+    triggering: GpeTriggering,
+    sink: S
+) -> Result<EventHandler, Status> {
+    require_full_hardware()?;
+
+    let gpe_device = gpe_device.raw();
+    let sink = Box::new(sink);
+    let handler: uacpi_sys::uacpi_gpe_handler = Some(gpe_sink_cb::<S>);
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_install_gpe_handler(
+            gpe_device,
+            index,
+            triggering as _,
+            handler,
+            &*sink as *const S as *mut c_void
+        ).into()
+    };
+
+    match status {
+        Status::Ok => Ok(EventHandler {
+            node: gpe_device,
+            handler: InstalledHandler::Gpe(index, handler),
+            _context: sink,
+        }),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Installs a handler for the GPE at `index` of `gpe_device` that pushes every occurrence into `sink`.
+/// `gpe_device` is the GPE block device, or the root node for the FADT GPE blocks.
+/// The GPE is left disabled after it fires, call finish_gpe once the event has been consumed.
+/// The handler stays installed until the returned guard is dropped.
+/// uACPI calls the handler from the SCI handler, which may run on any CPU.
+pub fn install_gpe_sink<S: EventSink + Send + Sync + 'static>(
+    _gpe_device: &NamespaceNode,
+    _index: u16,
+    _triggering: GpeTriggering,
+    _sink: S
+) -> Result<EventHandler, Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Enables the GPE at `index` of `gpe_device`.
+/// uACPI counts the users of every GPE, each call adds one and the GPE stays enabled
+/// until disable_gpe was called as many times, so drivers sharing a GPE don't need
+/// to coordinate. A _Lxx/_Exx method enabled by finalize_gpe_initialization counts as a user.
+/// uACPI has no API to read the number of users, gpe_status only tells whether
+/// the GPE is currently enabled.
+pub fn enable_gpe(gpe_device: &NamespaceNode, index: u16) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_enable_gpe(gpe_device.0, index).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Enables the GPE at `index` of `gpe_device`.
+pub fn enable_gpe(_gpe_device: &NamespaceNode, _index: u16) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Drops a user of the GPE at `index` of `gpe_device`, added by enable_gpe.
+/// The GPE is only disabled once it has no users left,
+/// and disabling a GPE that has no users fails.
+pub fn disable_gpe(gpe_device: &NamespaceNode, index: u16) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_disable_gpe(gpe_device.0, index).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Disables the GPE at `index` of `gpe_device`.
+pub fn disable_gpe(_gpe_device: &NamespaceNode, _index: u16) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Clears and re-enables a GPE that fired and was queued by a sink handler.
+pub fn finish_gpe(gpe_device: &NamespaceNode, index: u16) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_finish_handling_gpe(gpe_device.0, index).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Clears and re-enables a GPE that fired and was queued by a sink handler.
+pub fn finish_gpe(_gpe_device: &NamespaceNode, _index: u16) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+/// Returns the IRQ of the SCI (System Control Interrupt) from the FADT.
+/// uACPI installs its SCI handler on this IRQ through KernelApi::install_interrupt_handler
+/// during namespace_load, so the kernel doesn't have to route it manually.
+pub fn sci_irq() -> Result<u32, Status> {
+    table_fadt().map(|fadt| fadt.sci_int as u32)
+}
+
+/// How the SCI is wired to the interrupt controller, see sci_interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SciInterrupt {
+    /// The GSI the SCI arrives on, sci_irq unless an interrupt source override remaps it.
+    pub gsi: u32,
+    pub level_triggered: bool,
+    pub active_low: bool,
+}
+
+// MPS INTI flags of a MADT interrupt source override, 0 means conforming to the bus
+const INTI_POLARITY_MASK: u16 = 0b11;
+const INTI_ACTIVE_HIGH: u16 = 0b01;
+const INTI_ACTIVE_LOW: u16 = 0b11;
+const INTI_TRIGGER_MASK: u16 = 0b1100;
+const INTI_EDGE: u16 = 0b0100;
+const INTI_LEVEL: u16 = 0b1100;
+
+/// Returns how the kernel has to program the SCI in its interrupt controller. The SCI is
+/// a shareable, level triggered, active low interrupt, unless a MADT interrupt source
+/// override for sci_irq says otherwise or remaps it to a different GSI.
+/// uACPI installs and dispatches the SCI handler itself: KernelApi::install_interrupt_handler
+/// is called with sci_irq during namespace_load, and the kernel has to program that IRQ
+/// as described here for GPEs and fixed events to fire.
+pub fn sci_interrupt() -> Result<SciInterrupt, Status> {
+    let irq = sci_irq()?;
+    let mut sci = SciInterrupt { gsi: irq, level_triggered: true, active_low: true };
+
+    let madt = match table_find_by_signature(MADT_SIGNATURE) {
+        Ok(madt) => madt,
+        Err(Status::NotFound) => return Ok(sci),
+        Err(status) => return Err(status),
+    };
+
+    let iso = madt_entries(&madt).find_map(|entry| match entry {
+        MadtEntry::IrqSourceOverride(iso) if iso.source as u32 == irq => Some(*iso),
+        _ => None,
+    });
+
+    if let Some(iso) = iso {
+        let flags = u16::from_le(iso.flags);
+        sci.gsi = u32::from_le(iso.gsi);
+        match flags & INTI_POLARITY_MASK {
+            INTI_ACTIVE_HIGH => sci.active_low = false,
+            INTI_ACTIVE_LOW => sci.active_low = true,
+            _ => {}
+        }
+        match flags & INTI_TRIGGER_MASK {
+            INTI_EDGE => sci.level_triggered = false,
+            INTI_LEVEL => sci.level_triggered = true,
+            _ => {}
+        }
+    }
+
+    Ok(sci)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Enables all GPEs that have a _Lxx or _Exx method and are not used for wake.
+/// GPEs don't fire before this is called, the boot sequence is:
+/// 1. init
+/// 2. namespace_load, which installs the SCI handler on sci_irq
+/// 3. set_interrupt_model
+/// 4. namespace_initialize
+/// 5. install GPE handlers with install_gpe_sink, if any
+/// 6. finalize_gpe_initialization
+pub fn finalize_gpe_initialization() -> Result<(), Status> {
+    let status: Status = unsafe { uacpi_sys::uacpi_finalize_gpe_initialization().into() };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Enables all GPEs that have a _Lxx or _Exx method and are not used for wake.
+/// Reduced hardware systems have no GPEs.
+pub fn finalize_gpe_initialization() -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+/// The status of a GPE or fixed event as reported by gpe_status and fixed_event_status.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EventStatus(pub uacpi_sys::uacpi_event_info);
+
+impl EventStatus {
+    /// The event is enabled in software.
+    pub fn enabled(&self) -> bool {
+        self.0 & uacpi_sys::UACPI_EVENT_INFO_ENABLED != 0
+    }
+
+    /// The event is enabled for wake.
+    pub fn enabled_for_wake(&self) -> bool {
+        self.0 & uacpi_sys::UACPI_EVENT_INFO_ENABLED_FOR_WAKE != 0
+    }
+
+    /// The event is masked.
+    pub fn masked(&self) -> bool {
+        self.0 & uacpi_sys::UACPI_EVENT_INFO_MASKED != 0
+    }
+
+    /// The event has a handler or a method attached.
+    pub fn has_handler(&self) -> bool {
+        self.0 & uacpi_sys::UACPI_EVENT_INFO_HAS_HANDLER != 0
+    }
+
+    /// The enable bit of the event is set in hardware.
+    pub fn hw_enabled(&self) -> bool {
+        self.0 & uacpi_sys::UACPI_EVENT_INFO_HW_ENABLED != 0
+    }
+
+    /// The status bit of the event is set in hardware, i.e. the event is pending.
+    pub fn hw_status(&self) -> bool {
+        self.0 & uacpi_sys::UACPI_EVENT_INFO_HW_STATUS != 0
+    }
+}
+
+impl core::fmt::Debug for EventStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventStatus")
+            .field("enabled", &self.enabled())
+            .field("enabled_for_wake", &self.enabled_for_wake())
+            .field("masked", &self.masked())
+            .field("has_handler", &self.has_handler())
+            .field("hw_enabled", &self.hw_enabled())
+            .field("hw_status", &self.hw_status())
+            .finish()
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedEvent {
+    TimerStatus = uacpi_sys::UACPI_FIXED_EVENT_TIMER_STATUS,
+    PowerButton = uacpi_sys::UACPI_FIXED_EVENT_POWER_BUTTON,
+    SleepButton = uacpi_sys::UACPI_FIXED_EVENT_SLEEP_BUTTON,
+    Rtc = uacpi_sys::UACPI_FIXED_EVENT_RTC,
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Returns the software and hardware state of the GPE at `index` of `gpe_device`.
+pub fn gpe_status(gpe_device: &NamespaceNode, index: u16) -> Result<EventStatus, Status> {
+    require_full_hardware()?;
+
+    let mut info = 0;
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_gpe_info(gpe_device.0, index, &mut info).into()
+    };
+
+    match status {
+        Status::Ok => Ok(EventStatus(info)),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Returns the software and hardware state of the GPE at `index` of `gpe_device`.
+pub fn gpe_status(_gpe_device: &NamespaceNode, _index: u16) -> Result<EventStatus, Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Clears the status bit of the GPE at `index` of `gpe_device`, e.g. to drop a spurious event.
+pub fn clear_gpe(gpe_device: &NamespaceNode, index: u16) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_clear_gpe(gpe_device.0, index).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Clears the status bit of the GPE at `index` of `gpe_device`, e.g. to drop a spurious event.
+pub fn clear_gpe(_gpe_device: &NamespaceNode, _index: u16) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Returns the software and hardware state of a fixed event.
+pub fn fixed_event_status(event: FixedEvent) -> Result<EventStatus, Status> {
+    require_full_hardware()?;
+
+    let mut info = 0;
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_fixed_event_info(event as _, &mut info).into()
+    };
+
+    match status {
+        Status::Ok => Ok(EventStatus(info)),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Returns the software and hardware state of a fixed event.
+pub fn fixed_event_status(_event: FixedEvent) -> Result<EventStatus, Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Clears the status bit of a fixed event.
+pub fn clear_fixed_event(event: FixedEvent) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_clear_fixed_event(event as _).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Clears the status bit of a fixed event.
+pub fn clear_fixed_event(_event: FixedEvent) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Enables a fixed event, uACPI requires a handler to be installed for it first.
+pub fn enable_fixed_event(event: FixedEvent) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_enable_fixed_event(event as _).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Enables a fixed event, uACPI requires a handler to be installed for it first.
+pub fn enable_fixed_event(_event: FixedEvent) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Disables a fixed event, its handler stays installed.
+pub fn disable_fixed_event(event: FixedEvent) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_disable_fixed_event(event as _).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Disables a fixed event, its handler stays installed.
+pub fn disable_fixed_event(_event: FixedEvent) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Arms or disarms a fixed event as a wake source by writing its enable bit directly.
+/// Fixed events have no separate wake enable, the bit that enables the event also lets
+/// it wake the system, so this is meant for events that have no handler while running,
+/// e.g. an RTC alarm that is set right before sleeping. Events armed like this should
+/// be disarmed after waking, enable_fixed_event is the way to enable them while running.
+/// The PM timer can't wake the system and returns InvalidArgument.
+pub fn fixed_event_for_wake(event: FixedEvent, enable: bool) -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let field = match event {
+        FixedEvent::PowerButton => uacpi_sys::UACPI_REGISTER_FIELD_PWRBTN_EN,
+        FixedEvent::SleepButton => uacpi_sys::UACPI_REGISTER_FIELD_SLPBTN_EN,
+        FixedEvent::Rtc => uacpi_sys::UACPI_REGISTER_FIELD_RTC_EN,
+        FixedEvent::TimerStatus => return Err(Status::InvalidArgument),
+    };
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_write_register_field(field, enable as u64).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Arms or disarms a fixed event as a wake source by writing its enable bit directly.
+pub fn fixed_event_for_wake(_event: FixedEvent, _enable: bool) -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
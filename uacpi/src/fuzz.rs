@@ -0,0 +1,65 @@
+// Entry points for fuzzing the table parsers with arbitrary bytes, driven by the cargo fuzz
+// target in fuzz/, e.g. `cargo fuzz run tables`. The module only exists with cfg(fuzzing),
+// which cargo fuzz sets. Only the byte based parsers are driven, so none of this calls
+// into uACPI, the target links it like any other user of the crate.
+use crate::{
+    gtdt_entries_from_bytes, madt_entries_from_bytes,
+    mcfg_allocations_from_bytes, rsdt_entries_from_bytes, slit_distance_from_bytes,
+    xsdt_entries_from_bytes, GtdtEntry, MadtEntry,
+};
+
+/// Panics if `item` doesn't lie within `data`, i.e. a parser read out of bounds.
+fn check_within<T>(data: &[u8], item: &T) {
+    let start = item as *const T as usize;
+    let end = start + core::mem::size_of::<T>();
+    let range = data.as_ptr_range();
+    assert!(start >= range.start as usize && end <= range.end as usize);
+}
+
+/// Parses `data` as the table named by its signature and runs every parser to completion.
+/// Data without a known signature is ignored.
+pub fn fuzz_table(data: &[u8]) {
+    match data.get(..4) {
+        Some(b"RSDT") => rsdt_entries_from_bytes(data).for_each(drop),
+        Some(b"XSDT") => xsdt_entries_from_bytes(data).for_each(drop),
+        Some(b"APIC") => {
+            for entry in madt_entries_from_bytes(data) {
+                match entry {
+                    MadtEntry::Lapic(lapic) => check_within(data, lapic),
+                    MadtEntry::Ioapic(ioapic) => check_within(data, ioapic),
+                    MadtEntry::IrqSourceOverride(iso) => check_within(data, iso),
+                    MadtEntry::X2apic(x2apic) => check_within(data, x2apic),
+                    MadtEntry::Unknown(hdr) => check_within(data, hdr),
+                }
+            }
+        }
+        Some(b"MCFG") => {
+            for allocation in mcfg_allocations_from_bytes(data) {
+                check_within(data, allocation);
+            }
+        }
+        Some(b"SLIT") => {
+            let indices = (0..16).chain([u32::MAX as u64, u64::MAX]);
+            for from in indices.clone() {
+                for to in indices.clone() {
+                    let _ = slit_distance_from_bytes(data, from, to);
+                }
+            }
+        }
+        Some(b"GTDT") => {
+            for entry in gtdt_entries_from_bytes(data) {
+                match entry {
+                    GtdtEntry::Timer(block) => {
+                        check_within(data, block.timer());
+                        for frame in block.frames() {
+                            check_within(data, frame);
+                        }
+                    }
+                    GtdtEntry::Watchdog(watchdog) => check_within(data, watchdog),
+                    GtdtEntry::Unknown(hdr) => check_within(data, hdr),
+                }
+            }
+        }
+        _ => {}
+    }
+}
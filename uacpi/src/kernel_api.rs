@@ -1,20 +1,41 @@
 use crate::types::{
-    FirmwareRequest, Handle, IOAddr, LogLevel, PCIAddress, PhysAddr, Status, WorkType,
-    CpuFlags, ThreadId
+    FirmwareRequest, Handle, InterruptRet, IOAddr, LogLevel, PCIAddress, PhysAddr, Status, WorkType,
+    CpuFlags, ThreadId, MutexHandle, EventHandle, SpinlockHandle, FatalAction, FatalError
 };
 use alloc::{
     alloc::{alloc, dealloc},
     boxed::Box,
+    string::String,
     sync::Arc,
 };
 use core::{
     alloc::Layout,
     ffi::{c_char, c_void},
+    fmt::Debug,
 };
 use core::ffi::CStr;
 use log::{debug, error, info, trace, warn};
 
+#[cfg(feature = "single-core")]
+mod single_core;
+
+#[cfg(feature = "single-core")]
+pub use single_core::SingleCoreApi;
+
+#[cfg(feature = "test-util")]
+mod mock;
+
+#[cfg(feature = "test-util")]
+pub use mock::MockKernelApi;
+
 pub trait KernelApi {
+    /// Returns the physical address of the RSDP structure,
+    /// e.g. taken from the EFI configuration table or found by scanning the BIOS areas.
+    /// Only needed when initializing with init_auto.
+    fn get_rsdp(&self) -> Result<PhysAddr, Status> {
+        Err(Status::NotFound)
+    }
+
     /// Reads a value of the specified byte width (1, 2, 4 or 8) from memory.
     unsafe fn raw_memory_read(&self, phys: PhysAddr, byte_width: u8) -> Result<u64, Status>;
     /// Writes a value of the specified byte width (1, 2, 4 or 8) to memory.
@@ -35,7 +56,7 @@ pub trait KernelApi {
     /// Since PCI registers are 32 bits wide
     /// this must be able to handle e.g. 1-byte access by reading at the nearest
     /// 4-byte aligned offset below, then masking the value to select the target
-    /// byte.
+    /// byte, see pci_access::masked_read.
     unsafe fn pci_read(
         &self,
         address: PCIAddress,
@@ -47,7 +68,8 @@ pub trait KernelApi {
     /// Since PCI registers are 32 bits wide
     /// this must be able to handle e.g. 1-byte access by reading at the nearest
     /// 4-byte aligned offset below, then masking everything except the target byte
-    /// and writing that value with the value put in the target byte back.
+    /// and writing that value with the value put in the target byte back,
+    /// see pci_access::masked_write.
     unsafe fn pci_write(
         &self,
         address: PCIAddress,
@@ -56,8 +78,17 @@ pub trait KernelApi {
         val: u64,
     ) -> Result<(), Status>;
 
+    /// Returns the handle based access to the PCI configuration space uACPI 2.0 and later use,
+    /// which allows resolving the ECAM mapping of a device once instead of on every access.
+    /// Defaults to None, the devices are then accessed through pci_read and pci_write.
+    fn pci_devices(&self) -> Option<&dyn PciDeviceApi> {
+        None
+    }
+
     /// Maps a SystemIO address at [base, base + len] and return a handle
     /// that can be used for reading and writing to the IO range.
+    /// The crate checks the accesses uACPI makes through the handle
+    /// against `len` before passing them to io_read and io_write.
     unsafe fn io_map(&self, base: IOAddr, len: usize) -> Result<Handle, Status>;
     /// Unmaps an IO range previously mapped with io_map.
     unsafe fn io_unmap(&self, handle: Handle);
@@ -76,7 +107,10 @@ pub trait KernelApi {
 
     /// Creates a mapping used to access
     /// the physical range [phys, phys + len].
-    unsafe fn map(&self, phys: PhysAddr, len: usize) -> *mut c_void;
+    /// Returns an error, usually MappingFailed, if the range can't be mapped, the returned
+    /// pointer must not be null. uACPI only learns that mapping failed, not why, and fails
+    /// the operation that needed the mapping with MappingFailed.
+    unsafe fn map(&self, phys: PhysAddr, len: usize) -> Result<*mut c_void, Status>;
     /// Unmaps a mapping previously returned from map.
     unsafe fn unmap(&self, addr: *mut c_void, len: usize);
 
@@ -107,8 +141,23 @@ pub trait KernelApi {
     #[cfg(not(feature = "logging"))]
     fn log(&self, log_level: LogLevel, string: &str);
 
+    /// Receives every line logged by uACPI as passed by uACPI, before it is converted to a str.
+    /// Defaults to a lossy UTF-8 conversion passed on to log. Implementations that write
+    /// to a slow sink, e.g. a serial port while tracing AML, can override this to buffer
+    /// lines and flush them in batches, without the per line allocation of the conversion.
+    fn log_raw(&self, log_level: LogLevel, string: &CStr) {
+        self.log(log_level, &String::from_utf8_lossy(string.to_bytes()))
+    }
+
     /// Returns the monotonic count of 100 nanosecond ticks elapsed since boot.
+    /// Used by uACPI versions before 1.0.
     fn get_ticks(&self) -> u64;
+    /// Returns the monotonic count of nanoseconds elapsed since boot.
+    /// Used by uACPI 1.0 and later, which replaced get_ticks with this.
+    /// Defaults to get_ticks scaled to nanoseconds.
+    fn get_nanoseconds_since_boot(&self) -> u64 {
+        self.get_ticks().saturating_mul(100)
+    }
 
     /// Spins for the specified amount of microseconds.
     fn stall(&self, usec: u8);
@@ -116,50 +165,84 @@ pub trait KernelApi {
     fn sleep(&self, msec: u8);
 
     /// Creates a non-recursive kernel mutex.
-    fn create_mutex(&self) -> Handle;
+    fn create_mutex(&self) -> MutexHandle;
     /// Destroys a mutex previously created by create_mutex.
-    fn destroy_mutex(&self, mutex: Handle);
+    fn destroy_mutex(&self, mutex: MutexHandle);
     /// Tries to acquire a mutex with a millisecond timeout.
     /// A timeout value of 0xFFFF implies infinite wait.
-    fn acquire_mutex(&self, mutex: Handle, timeout: u16) -> bool;
+    fn acquire_mutex(&self, mutex: MutexHandle, timeout: u16) -> bool;
     /// Releases a previously acquired mutex.
-    fn release_mutex(&self, mutex: Handle);
+    fn release_mutex(&self, mutex: MutexHandle);
 
     /// Creates a spinlock.
-    fn create_spinlock(&self) -> Handle;
+    fn create_spinlock(&self) -> SpinlockHandle;
     /// Destroys a spinlock previously created by create_spinlock.
-    fn destroy_spinlock(&self, lock: Handle);
+    fn destroy_spinlock(&self, lock: SpinlockHandle);
     /// Disables interrupts and acquires a spinlock.
     /// Returns the previous state of cpu flags that
     /// can be used to restore the interrupt state when the lock is released.
-    fn acquire_spinlock(&self, lock: Handle) -> CpuFlags;
+    fn acquire_spinlock(&self, lock: SpinlockHandle) -> CpuFlags;
     /// Releases a spinlock and restores the previous interrupt state.
-    fn release_spinlock(&self, lock: Handle, cpu_flags: CpuFlags);
+    fn release_spinlock(&self, lock: SpinlockHandle, cpu_flags: CpuFlags);
 
-    /// Creates a semaphore-line event object.
-    fn create_event(&self) -> Handle;
+    /// Creates a semaphore-like event object.
+    /// Every event owns an unsigned counter that starts at 0.
+    fn create_event(&self) -> EventHandle;
     /// Destroys an event previously created by create_event.
-    fn destroy_event(&self, event: Handle);
+    fn destroy_event(&self, event: EventHandle);
     /// Waits for an event (counter > 0) with a millisecond timeout.
+    /// While the counter is 0 the caller blocks until it is signaled or the timeout expires,
+    /// a timeout of 0 never blocks and only checks the counter once.
     /// A timeout value of 0xFFFF implies infinite wait.
-    /// The internal counter is decremented by 1 if the wait was successful.
+    /// The internal counter is decremented by exactly 1 if the wait was successful
+    /// and is left untouched otherwise.
     /// A successful wait is indicated by returning true.
-    fn wait_for_event(&self, event: Handle, timeout: u16) -> bool;
+    fn wait_for_event(&self, event: EventHandle, timeout: u16) -> bool;
     /// Signals an event by incrementing its internal counter by 1.
+    /// Signals must not be coalesced, N signals allow N successful waits.
+    /// The counter should saturate instead of wrapping around.
     /// This functions may be used in interrupt contexts.
-    fn signal_event(&self, event: Handle);
+    fn signal_event(&self, event: EventHandle);
     /// Resets an event by setting its internal counter to 0.
-    fn reset_event(&self, event: Handle);
+    fn reset_event(&self, event: EventHandle);
+    /// Returns the current value of the internal counter of an event.
+    /// uACPI never calls this, it exists for inspecting an implementation
+    /// from tests or polling code. Returns None if the implementation
+    /// does not support peeking at the counter.
+    fn event_count(&self, _event: EventHandle) -> Option<u64> {
+        None
+    }
 
     /// Returns a unique identifier of the currently executing thread.
+    /// uACPI uses it to track the owner and sync level of AML mutexes,
+    /// returning the same id on different CPUs breaks concurrent eval.
     fn get_thread_id(&self) -> ThreadId;
 
-    /// Handles a firmware request.
+    /// Handles a Breakpoint or a request type unknown to this crate,
+    /// Fatal requests are passed to fatal_error instead.
+    /// A release kernel has no debugger to enter and should usually just return Ok.
     fn firmware_request(&self, req: FirmwareRequest) -> Result<(), Status>;
 
+    /// Handles the AML Fatal operator. The spec expects the OS to log the error and shut down,
+    /// but since the values are OEM defined, whether to continue is up to the kernel.
+    fn fatal_error(&self, error: FatalError) -> FatalAction;
+
+    /// Stops the machine after fatal_error returned Halt.
+    /// Defaults to spinning forever, a kernel will usually want to stop the other CPUs
+    /// and power off instead.
+    fn halt(&self) -> ! {
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+
     /// Installs an interrupt handler for `irq`.
+    /// The handler reports whether the interrupt was raised by ACPI, so a shared
+    /// line can be passed on to the next handler when it returns NotHandled.
     /// The returned handle can be used to refer to this handler from other API.
-    fn install_interrupt_handler(&self, irq: u32, handler: Box<dyn Fn()>,
+    /// uACPI calls this for the SCI during namespace_load, see sci_interrupt for how
+    /// the SCI has to be programmed.
+    fn install_interrupt_handler(&self, irq: u32, handler: Box<dyn Fn() -> InterruptRet>,
     ) -> Result<Handle, Status>;
     /// Uninstalls an interrupt handler
     /// previously installed with install_interrupt_handler.
@@ -167,35 +250,244 @@ pub trait KernelApi {
 
     /// Schedules deferred work for execution.
     /// Might be invoked from an interrupt context.
-    fn schedule_work(&self, work_type: WorkType, handler: Box<dyn Fn()>) -> Result<(), Status>;
+    /// GPE execution work should run on CPU 0 to avoid SMI related firmware bugs,
+    /// notification work may run anywhere.
+    /// A running handler may schedule more work, e.g. a GPE method that executes Notify.
+    /// The implementation owns `work` and must run it exactly once, see Work.
+    fn schedule_work(&self, work_type: WorkType, work: Work) -> Result<(), Status>;
 
-    /// Blocks until all scheduled work is complete and the work queue is empty.
+    /// Blocks until all scheduled work is complete and the work queue is empty,
+    /// including work that was scheduled by other work while waiting.
+    /// uACPI doesn't distinguish work types here, both GPE execution and notification
+    /// work has to be flushed.
+    ///
+    /// uACPI calls this from API functions such as uninstalling a notify or GPE handler
+    /// and preparing for sleep. This is never called from within a work handler by uACPI
+    /// itself, but a handler that calls one of those functions would wait for itself,
+    /// so implementations may detect that case and return an error instead of deadlocking.
     fn wait_for_work_completion(&self) -> Result<(), Status>;
 }
 
+/// Handle based access to the configuration space of PCI devices, see KernelApi::pci_devices.
+/// The handles are only ever passed back to the implementation that returned them.
+pub trait PciDeviceApi {
+    /// Opens the configuration space of the PCI device at `address`.
+    ///
+    /// # Safety
+    /// Accesses the configuration space of an arbitrary device.
+    unsafe fn pci_device_open(&self, address: PCIAddress) -> Result<Handle, Status>;
+    /// Closes a device previously opened with pci_device_open.
+    ///
+    /// # Safety
+    /// `device` must have been returned by pci_device_open and must not be used afterwards.
+    unsafe fn pci_device_close(&self, device: Handle);
+    /// Reads a value of the specified byte width (1, 2 or 4) from a 0-based offset
+    /// within the configuration space of a device opened with pci_device_open.
+    ///
+    /// # Safety
+    /// `device` must have been returned by pci_device_open and not been closed yet.
+    unsafe fn pci_device_read(&self, device: Handle, offset: usize, byte_width: u8) -> Result<u64, Status>;
+    /// Writes a value of the specified byte width (1, 2 or 4) to a 0-based offset
+    /// within the configuration space of a device opened with pci_device_open.
+    ///
+    /// # Safety
+    /// `device` must have been returned by pci_device_open and not been closed yet.
+    unsafe fn pci_device_write(
+        &self,
+        device: Handle,
+        offset: usize,
+        byte_width: u8,
+        val: u64,
+    ) -> Result<(), Status>;
+}
+
+/// A mapping of a physical range created with KernelApi::map that is unmapped on drop.
+pub struct Mapping<'a> {
+    api: &'a dyn KernelApi,
+    ptr: *mut c_void,
+    len: usize,
+}
+
+impl<'a> Mapping<'a> {
+    /// Maps the physical range [phys, phys + len].
+    /// Returns the error of KernelApi::map, or MappingFailed if it returned a null pointer.
+    ///
+    /// # Safety
+    /// The range must be safe to access for as long as the mapping lives.
+    pub unsafe fn new(api: &'a dyn KernelApi, phys: PhysAddr, len: usize) -> Result<Self, Status> {
+        let ptr = api.map(phys, len)?;
+        if ptr.is_null() {
+            return Err(Status::MappingFailed);
+        }
+        Ok(Self { api, ptr, len })
+    }
+
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.ptr
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Performs a volatile read of a `T` at `offset` bytes into the mapping.
+    /// Returns None if the value is out of bounds or misaligned.
+    pub fn read<T: Copy>(&self, offset: usize) -> Option<T> {
+        let ptr = self.checked_ptr::<T>(offset)?;
+        Some(unsafe { ptr.read_volatile() })
+    }
+
+    /// Performs a volatile write of a `T` at `offset` bytes into the mapping.
+    /// Returns None if the value is out of bounds or misaligned.
+    pub fn write<T: Copy>(&mut self, offset: usize, val: T) -> Option<()> {
+        let ptr = self.checked_ptr::<T>(offset)?;
+        unsafe { ptr.write_volatile(val) };
+        Some(())
+    }
+
+    fn checked_ptr<T>(&self, offset: usize) -> Option<*mut T> {
+        let end = offset.checked_add(core::mem::size_of::<T>())?;
+        if end > self.len {
+            return None;
+        }
+        let ptr = unsafe { self.ptr.cast::<u8>().add(offset) }.cast::<T>();
+        if !ptr.is_aligned() {
+            return None;
+        }
+        Some(ptr)
+    }
+}
+
+impl Drop for Mapping<'_> {
+    fn drop(&mut self) {
+        unsafe { self.api.unmap(self.ptr, self.len) }
+    }
+}
+
+/// Deferred work scheduled by uACPI through KernelApi::schedule_work.
+/// It wraps the raw C callback and its context, running it consumes the work,
+/// so the callback can't run twice and nothing is left to free afterwards.
+/// Dropping it without running it loses the work, which uACPI expects to complete.
+#[must_use = "scheduled work must be run"]
+pub struct Work {
+    handler: extern "C" fn(Handle),
+    ctx: Handle,
+}
+
+// The callback is plain C code and uACPI expects it to run on any CPU.
+unsafe impl Send for Work {}
+
+impl Work {
+    /// Runs the uACPI callback, may be called from any thread.
+    pub fn run(self) {
+        (self.handler)(self.ctx)
+    }
+}
+
+impl Debug for Work {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Work").field("ctx", &self.ctx).finish_non_exhaustive()
+    }
+}
+
+/// A spinlock acquired with KernelApi::acquire_spinlock that is released on drop,
+/// restoring the interrupt state it was acquired with.
+pub struct SpinlockGuard<'a> {
+    api: &'a dyn KernelApi,
+    lock: SpinlockHandle,
+    flags: CpuFlags,
+}
+
+impl<'a> SpinlockGuard<'a> {
+    /// Disables interrupts and acquires `lock`.
+    pub fn new(api: &'a dyn KernelApi, lock: SpinlockHandle) -> Self {
+        let flags = api.acquire_spinlock(lock);
+        Self { api, lock, flags }
+    }
+
+    pub fn lock(&self) -> SpinlockHandle {
+        self.lock
+    }
+}
+
+impl Drop for SpinlockGuard<'_> {
+    fn drop(&mut self) {
+        self.api.release_spinlock(self.lock, self.flags)
+    }
+}
+
+/// Aborts when a panic unwinds through it.
+/// The `#[no_mangle]` functions below are called by uACPI's C code and unwinding
+/// into it is undefined behavior. `catch_unwind` is not available in no_std,
+/// so a panic in a callback is turned into an abort by panicking again while unwinding.
+struct AbortOnUnwind;
+
+impl Drop for AbortOnUnwind {
+    fn drop(&mut self) {
+        panic!("panic in a uACPI kernel API callback, aborting instead of unwinding into C");
+    }
+}
+
+pub(crate) fn no_unwind<R>(f: impl FnOnce() -> R) -> R {
+    let guard = AbortOnUnwind;
+    let ret = f();
+    core::mem::forget(guard);
+    ret
+}
+
+/// Checks that `byte_width` is a power of two access width no larger than `max`.
+pub(crate) fn is_valid_width(byte_width: u8, max: u8) -> bool {
+    matches!(byte_width, 1 | 2 | 4 | 8) && byte_width <= max
+}
+
 static mut KERNEL_API: Option<Arc<dyn KernelApi>> = None;
 
 pub fn set_kernel_api(api: Arc<dyn KernelApi>) {
     unsafe { KERNEL_API = Some(api) }
 }
 
-fn get_kernel_api() -> Arc<dyn KernelApi> {
+pub(crate) fn get_kernel_api() -> Arc<dyn KernelApi> {
     unsafe { KERNEL_API.as_ref().expect("No kernel api set").clone() }
 }
 
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_get_rsdp(
+    out_rsdp_address: *mut uacpi_sys::uacpi_phys_addr,
+) -> Status {
+    no_unwind(|| {
+        match get_kernel_api().get_rsdp() {
+            Ok(rsdp) => {
+                *out_rsdp_address = rsdp.0;
+                Status::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_raw_memory_read(
     phys: uacpi_sys::uacpi_phys_addr,
     byte_width: u8,
     val: *mut u64,
 ) -> Status {
-    match get_kernel_api().raw_memory_read(PhysAddr(phys), byte_width) {
-        Ok(ret) => {
-            *val = ret;
-            Status::Ok
+    no_unwind(|| {
+        if !is_valid_width(byte_width, 8) {
+            return Status::InvalidArgument;
         }
-        Err(status) => status,
-    }
+
+        match get_kernel_api().raw_memory_read(PhysAddr(phys), byte_width) {
+            Ok(ret) => {
+                *val = ret;
+                Status::Ok
+            }
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -204,10 +496,16 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_raw_memory_write(
     byte_width: u8,
     val: u64,
 ) -> Status {
-    match get_kernel_api().raw_memory_write(PhysAddr(phys), byte_width, val) {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
-    }
+    no_unwind(|| {
+        if !is_valid_width(byte_width, 8) {
+            return Status::InvalidArgument;
+        }
+
+        match get_kernel_api().raw_memory_write(PhysAddr(phys), byte_width, val) {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -216,13 +514,19 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_raw_io_read(
     byte_width: u8,
     val: *mut u64,
 ) -> Status {
-    match get_kernel_api().raw_io_read(IOAddr(addr), byte_width) {
-        Ok(ret) => {
-            *val = ret;
-            Status::Ok
+    no_unwind(|| {
+        if !is_valid_width(byte_width, 4) {
+            return Status::InvalidArgument;
         }
-        Err(status) => status,
-    }
+
+        match get_kernel_api().raw_io_read(IOAddr(addr), byte_width) {
+            Ok(ret) => {
+                *val = ret;
+                Status::Ok
+            }
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -231,10 +535,16 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_raw_io_write(
     byte_width: u8,
     val: u64,
 ) -> Status {
-    match get_kernel_api().raw_io_write(IOAddr(addr), byte_width, val) {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
-    }
+    no_unwind(|| {
+        if !is_valid_width(byte_width, 4) {
+            return Status::InvalidArgument;
+        }
+
+        match get_kernel_api().raw_io_write(IOAddr(addr), byte_width, val) {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -244,13 +554,19 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_pci_read(
     byte_width: u8,
     val: *mut u64,
 ) -> Status {
-    match get_kernel_api().pci_read(PCIAddress(*address), offset, byte_width) {
-        Ok(ret) => {
-            *val = ret;
-            Status::Ok
+    no_unwind(|| {
+        if !is_valid_width(byte_width, 4) {
+            return Status::InvalidArgument;
         }
-        Err(status) => status,
-    }
+
+        match get_kernel_api().pci_read(PCIAddress(*address), offset, byte_width) {
+            Ok(ret) => {
+                *val = ret;
+                Status::Ok
+            }
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -260,9 +576,188 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_pci_write(
     byte_width: u8,
     val: u64,
 ) -> Status {
-    match get_kernel_api().pci_write(PCIAddress(*address), offset, byte_width, val) {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
+    no_unwind(|| {
+        if !is_valid_width(byte_width, 4) {
+            return Status::InvalidArgument;
+        }
+
+        match get_kernel_api().pci_write(PCIAddress(*address), offset, byte_width, val) {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
+}
+
+/// A PCI device opened on behalf of uACPI. The handle passed to uACPI points to this,
+/// which records whether the device was opened through KernelApi::pci_devices,
+/// so a handle is never passed to an implementation that didn't return it.
+struct PciDevice {
+    address: PCIAddress,
+    /// None if the device is accessed through pci_read and pci_write.
+    handle: Option<Handle>,
+}
+
+impl PciDevice {
+    /// # Safety
+    /// `handle` must have been returned by uacpi_kernel_pci_device_open and not been closed yet.
+    unsafe fn from_raw<'a>(handle: uacpi_sys::uacpi_handle) -> &'a PciDevice {
+        &*(handle as *const PciDevice)
+    }
+
+    unsafe fn read(&self, api: &dyn KernelApi, offset: usize, byte_width: u8) -> Result<u64, Status> {
+        match (self.handle, api.pci_devices()) {
+            (Some(handle), Some(devices)) => devices.pci_device_read(handle, offset, byte_width),
+            (None, _) => api.pci_read(self.address, offset, byte_width),
+            (Some(_), None) => Err(Status::InternalError),
+        }
+    }
+
+    unsafe fn write(&self, api: &dyn KernelApi, offset: usize, byte_width: u8, val: u64) -> Result<(), Status> {
+        match (self.handle, api.pci_devices()) {
+            (Some(handle), Some(devices)) => devices.pci_device_write(handle, offset, byte_width, val),
+            (None, _) => api.pci_write(self.address, offset, byte_width, val),
+            (Some(_), None) => Err(Status::InternalError),
+        }
+    }
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_device_open(
+    address: uacpi_sys::uacpi_pci_address,
+    out_handle: *mut uacpi_sys::uacpi_handle,
+) -> Status {
+    no_unwind(|| {
+        let address = PCIAddress(address);
+        let handle = match get_kernel_api().pci_devices() {
+            Some(devices) => match devices.pci_device_open(address) {
+                Ok(handle) => Some(handle),
+                Err(status) => return status,
+            },
+            None => None,
+        };
+
+        *out_handle = Box::into_raw(Box::new(PciDevice { address, handle })).cast();
+        Status::Ok
+    })
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_device_close(handle: uacpi_sys::uacpi_handle) {
+    no_unwind(|| {
+        let device = Box::from_raw(handle as *mut PciDevice);
+        if let (Some(handle), Some(devices)) = (device.handle, get_kernel_api().pci_devices()) {
+            devices.pci_device_close(handle)
+        }
+    })
+}
+
+/// Shared part of the sized uACPI 2.0 PCI read shims.
+unsafe fn pci_device_read<T: TryFrom<u64>>(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    val: *mut T,
+) -> Status {
+    no_unwind(|| {
+        let byte_width = core::mem::size_of::<T>() as u8;
+        match PciDevice::from_raw(handle).read(&*get_kernel_api(), offset, byte_width) {
+            Ok(ret) => match T::try_from(ret) {
+                Ok(ret) => {
+                    *val = ret;
+                    Status::Ok
+                }
+                Err(_) => Status::InternalError,
+            },
+            Err(status) => status,
+        }
+    })
+}
+
+/// Shared part of the sized uACPI 2.0 PCI write shims.
+unsafe fn pci_device_write(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    byte_width: u8,
+    val: u64,
+) -> Status {
+    no_unwind(|| {
+        match PciDevice::from_raw(handle).write(&*get_kernel_api(), offset, byte_width, val) {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_read8(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    val: *mut u8,
+) -> Status {
+    pci_device_read(handle, offset, val)
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_read16(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    val: *mut u16,
+) -> Status {
+    pci_device_read(handle, offset, val)
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_read32(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    val: *mut u32,
+) -> Status {
+    pci_device_read(handle, offset, val)
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_write8(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    val: u8,
+) -> Status {
+    pci_device_write(handle, offset, 1, val.into())
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_write16(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    val: u16,
+) -> Status {
+    pci_device_write(handle, offset, 2, val.into())
+}
+
+#[no_mangle]
+pub(crate) unsafe extern "C" fn uacpi_kernel_pci_write32(
+    handle: uacpi_sys::uacpi_handle,
+    offset: usize,
+    val: u32,
+) -> Status {
+    pci_device_write(handle, offset, 4, val.into())
+}
+
+/// An IO range mapped on behalf of uACPI. The handle passed to uACPI points to this,
+/// so the shims can reject accesses outside of the mapped length before they reach io_read.
+struct IoMapping {
+    handle: Handle,
+    len: usize,
+}
+
+impl IoMapping {
+    /// # Safety
+    /// `handle` must have been returned by uacpi_kernel_io_map and not been unmapped yet.
+    unsafe fn from_raw<'a>(handle: uacpi_sys::uacpi_handle) -> &'a IoMapping {
+        &*(handle as *const IoMapping)
+    }
+
+    fn contains(&self, offset: usize, byte_width: u8) -> bool {
+        is_valid_width(byte_width, 4)
+            && offset.checked_add(byte_width as usize).is_some_and(|end| end <= self.len)
     }
 }
 
@@ -272,18 +767,23 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_io_map(
     len: usize,
     out_handle: *mut uacpi_sys::uacpi_handle,
 ) -> Status {
-    match get_kernel_api().io_map(IOAddr(base), len) {
-        Ok(ret) => {
-            *out_handle = ret.0;
-            Status::Ok
+    no_unwind(|| {
+        match get_kernel_api().io_map(IOAddr(base), len) {
+            Ok(handle) => {
+                *out_handle = Box::into_raw(Box::new(IoMapping { handle, len })).cast();
+                Status::Ok
+            }
+            Err(status) => status,
         }
-        Err(status) => status,
-    }
+    })
 }
 
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_io_unmap(handle: uacpi_sys::uacpi_handle) {
-    get_kernel_api().io_unmap(Handle(handle))
+    no_unwind(|| {
+        let mapping = Box::from_raw(handle as *mut IoMapping);
+        get_kernel_api().io_unmap(mapping.handle)
+    })
 }
 
 #[no_mangle]
@@ -293,13 +793,20 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_io_read(
     byte_width: u8,
     val: *mut u64,
 ) -> Status {
-    match get_kernel_api().io_read(Handle(handle), offset, byte_width) {
-        Ok(ret) => {
-            *val = ret;
-            Status::Ok
+    no_unwind(|| {
+        let mapping = IoMapping::from_raw(handle);
+        if !mapping.contains(offset, byte_width) {
+            return Status::InvalidArgument;
         }
-        Err(status) => status,
-    }
+
+        match get_kernel_api().io_read(mapping.handle, offset, byte_width) {
+            Ok(ret) => {
+                *val = ret;
+                Status::Ok
+            }
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -309,10 +816,17 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_io_write(
     byte_width: u8,
     val: u64,
 ) -> Status {
-    match get_kernel_api().io_write(Handle(handle), offset, byte_width, val) {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
-    }
+    no_unwind(|| {
+        let mapping = IoMapping::from_raw(handle);
+        if !mapping.contains(offset, byte_width) {
+            return Status::InvalidArgument;
+        }
+
+        match get_kernel_api().io_write(mapping.handle, offset, byte_width, val) {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -320,152 +834,200 @@ pub(crate) unsafe extern "C" fn uacpi_kernel_map(
     phys: uacpi_sys::uacpi_phys_addr,
     len: usize,
 ) -> *mut c_void {
-    get_kernel_api().map(PhysAddr(phys), len)
+    // uACPI checks for null and turns it into UACPI_STATUS_MAPPING_FAILED
+    no_unwind(|| get_kernel_api().map(PhysAddr(phys), len).unwrap_or(core::ptr::null_mut()))
 }
 
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_unmap(addr: *mut c_void, len: usize) {
-    get_kernel_api().unmap(addr, len)
+    no_unwind(|| get_kernel_api().unmap(addr, len))
+}
+
+/// The alignment of every uACPI allocation, the alignment of max_align_t on the
+/// 64-bit targets uACPI supports, which malloc guarantees and uACPI may rely on.
+const ALLOC_ALIGN: usize = 16;
+
+/// Returns the layout of a uACPI allocation, None if the size is too large for a layout.
+/// uACPI is built with UACPI_SIZED_FREES, so free gets the same size as the allocation
+/// and this reconstructs the exact layout it was allocated with.
+fn alloc_layout(size: usize) -> Option<Layout> {
+    Layout::from_size_align(size, ALLOC_ALIGN).ok()
 }
 
+// uACPI treats a null return as OUT_OF_MEMORY, so requests that can't be
+// satisfied, e.g. absurd sizes from broken AML, fail gracefully instead of panicking.
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_alloc(size: usize) -> *mut c_void {
-    get_kernel_api()
-        .alloc(Layout::from_size_align(size, 8).unwrap())
-        .cast()
+    no_unwind(|| {
+        match alloc_layout(size) {
+            Some(layout) => get_kernel_api().alloc(layout).cast(),
+            None => core::ptr::null_mut(),
+        }
+    })
 }
 
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_calloc(count: usize, size: usize) -> *mut c_void {
-    get_kernel_api()
-        .alloc(Layout::from_size_align(count * size, 8).unwrap())
-        .cast()
+    no_unwind(|| {
+        match count.checked_mul(size).and_then(alloc_layout) {
+            Some(layout) => get_kernel_api().alloc(layout).cast(),
+            None => core::ptr::null_mut(),
+        }
+    })
 }
 
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_free(ptr: *mut c_void, size: usize) {
-    if !ptr.is_null() {
-        get_kernel_api().dealloc(ptr.cast(), Layout::from_size_align(size, 8).unwrap())
-    }
+    no_unwind(|| {
+        if ptr.is_null() {
+            return;
+        }
+        // a size without a layout can't have been allocated, so there is nothing to free
+        if let Some(layout) = alloc_layout(size) {
+            get_kernel_api().dealloc(ptr.cast(), layout)
+        }
+    })
 }
 
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_log(
     log_level: uacpi_sys::uacpi_log_level,
     str: *const c_char) {
-    let s = CStr::from_ptr(str);
-    get_kernel_api().log(LogLevel(log_level), s.to_str().unwrap());
+    no_unwind(|| {
+        // an unknown level would match none of the levels a logger checks for
+        // and the line would be dropped, so it's logged as a warning instead
+        let log_level = LogLevel::from_raw(log_level).unwrap_or(LogLevel::WARN);
+        get_kernel_api().log_raw(log_level, CStr::from_ptr(str));
+    })
 }
 
 #[no_mangle]
 pub(crate) extern "C" fn uacpi_kernel_get_ticks() -> u64 {
-    get_kernel_api().get_ticks()
+    no_unwind(|| get_kernel_api().get_ticks())
+}
+
+#[no_mangle]
+pub(crate) extern "C" fn uacpi_kernel_get_nanoseconds_since_boot() -> u64 {
+    no_unwind(|| get_kernel_api().get_nanoseconds_since_boot())
 }
 
 #[no_mangle]
 pub(crate) extern "C" fn uacpi_kernel_stall(usec: u8) {
-    get_kernel_api().stall(usec)
+    no_unwind(|| get_kernel_api().stall(usec))
 }
 
 #[no_mangle]
 pub(crate) extern "C" fn uacpi_kernel_sleep(msec: u8) {
-    get_kernel_api().sleep(msec)
+    no_unwind(|| get_kernel_api().sleep(msec))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_create_mutex() -> Handle {
-    get_kernel_api().create_mutex()
+pub(crate) extern "C" fn uacpi_kernel_create_mutex() -> MutexHandle {
+    no_unwind(|| get_kernel_api().create_mutex())
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_free_mutex(mutex: Handle) {
-    get_kernel_api().destroy_mutex(mutex)
+pub(crate) extern "C" fn uacpi_kernel_free_mutex(mutex: MutexHandle) {
+    no_unwind(|| get_kernel_api().destroy_mutex(mutex))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_acquire_mutex(mutex: Handle, timeout: u16) -> bool {
-    get_kernel_api().acquire_mutex(mutex, timeout)
+pub(crate) extern "C" fn uacpi_kernel_acquire_mutex(mutex: MutexHandle, timeout: u16) -> bool {
+    no_unwind(|| get_kernel_api().acquire_mutex(mutex, timeout))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_release_mutex(mutex: Handle) {
-    get_kernel_api().release_mutex(mutex)
+pub(crate) extern "C" fn uacpi_kernel_release_mutex(mutex: MutexHandle) {
+    no_unwind(|| get_kernel_api().release_mutex(mutex))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_create_event() -> Handle {
-    get_kernel_api().create_event()
+pub(crate) extern "C" fn uacpi_kernel_create_event() -> EventHandle {
+    no_unwind(|| get_kernel_api().create_event())
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_free_event(event: Handle) {
-    get_kernel_api().destroy_event(event)
+pub(crate) extern "C" fn uacpi_kernel_free_event(event: EventHandle) {
+    no_unwind(|| get_kernel_api().destroy_event(event))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_wait_for_event(event: Handle, timeout: u16) -> bool {
-    get_kernel_api().wait_for_event(event, timeout)
+pub(crate) extern "C" fn uacpi_kernel_wait_for_event(event: EventHandle, timeout: u16) -> bool {
+    no_unwind(|| get_kernel_api().wait_for_event(event, timeout))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_signal_event(event: Handle) {
-    get_kernel_api().signal_event(event)
+pub(crate) extern "C" fn uacpi_kernel_signal_event(event: EventHandle) {
+    no_unwind(|| get_kernel_api().signal_event(event))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_reset_event(event: Handle) {
-    get_kernel_api().reset_event(event)
+pub(crate) extern "C" fn uacpi_kernel_reset_event(event: EventHandle) {
+    no_unwind(|| get_kernel_api().reset_event(event))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_create_spinlock() -> Handle {
-    get_kernel_api().create_spinlock()
+pub(crate) extern "C" fn uacpi_kernel_create_spinlock() -> SpinlockHandle {
+    no_unwind(|| get_kernel_api().create_spinlock())
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_free_spinlock(lock: Handle) {
-    get_kernel_api().destroy_spinlock(lock)
+pub(crate) extern "C" fn uacpi_kernel_free_spinlock(lock: SpinlockHandle) {
+    no_unwind(|| get_kernel_api().destroy_spinlock(lock))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_spinlock_lock(lock: Handle) -> CpuFlags {
-    get_kernel_api().acquire_spinlock(lock)
+pub(crate) extern "C" fn uacpi_kernel_spinlock_lock(lock: SpinlockHandle) -> CpuFlags {
+    no_unwind(|| get_kernel_api().acquire_spinlock(lock))
 }
 
 #[no_mangle]
-pub(crate) extern "C" fn uacpi_kernel_spinlock_unlock(lock: Handle, cpu_flags: CpuFlags) {
-    get_kernel_api().release_spinlock(lock, cpu_flags)
+pub(crate) extern "C" fn uacpi_kernel_spinlock_unlock(lock: SpinlockHandle, cpu_flags: CpuFlags) {
+    no_unwind(|| get_kernel_api().release_spinlock(lock, cpu_flags))
 }
 
 #[no_mangle]
 pub(crate) extern "C" fn uacpi_kernel_get_thread_id() -> ThreadId {
-    get_kernel_api().get_thread_id()
+    no_unwind(|| get_kernel_api().get_thread_id())
 }
 
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_handle_firmware_request(
     req: *const uacpi_sys::uacpi_firmware_request,
 ) -> Status {
-    match get_kernel_api().firmware_request(req.read().into()) {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
-    }
+    no_unwind(|| {
+        let api = get_kernel_api();
+        let req: FirmwareRequest = req.read().into();
+
+        match req.as_fatal() {
+            Some(error) => match api.fatal_error(error) {
+                FatalAction::Continue => Status::Ok,
+                FatalAction::Halt => api.halt(),
+            },
+            None => match api.firmware_request(req) {
+                Ok(()) => Status::Ok,
+                Err(status) => status,
+            },
+        }
+    })
 }
 
 #[no_mangle]
 pub(crate) unsafe extern "C" fn uacpi_kernel_install_interrupt_handler(
     irq: u32,
-    handler: extern "C" fn(Handle),
+    handler: extern "C" fn(Handle) -> uacpi_sys::uacpi_interrupt_ret,
     ctx: Handle,
     out_irq_handle: *mut Handle,
 ) -> Status {
-    match get_kernel_api().install_interrupt_handler(irq, Box::new(move || handler(ctx))) {
-        Ok(val) => {
-            *out_irq_handle = val;
-            Status::Ok
+    no_unwind(|| {
+        match get_kernel_api().install_interrupt_handler(irq, Box::new(move || handler(ctx).into())) {
+            Ok(val) => {
+                *out_irq_handle = val;
+                Status::Ok
+            }
+            Err(status) => status,
         }
-        Err(status) => status,
-    }
+    })
 }
 
 #[no_mangle]
@@ -473,10 +1035,12 @@ pub(crate) extern "C" fn uacpi_kernel_uninstall_interrupt_handler(
     _handler: extern "C" fn(Handle),
     irq_handle: Handle,
 ) -> Status {
-    match get_kernel_api().uninstall_interrupt_handler(irq_handle) {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
-    }
+    no_unwind(|| {
+        match get_kernel_api().uninstall_interrupt_handler(irq_handle) {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
@@ -485,16 +1049,49 @@ pub(crate) extern "C" fn uacpi_kernel_schedule_work(
     handler: extern "C" fn(Handle),
     ctx: Handle,
 ) -> Status {
-    match get_kernel_api().schedule_work(work_type, Box::new(move || handler(ctx))) {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
-    }
+    no_unwind(|| {
+        match get_kernel_api().schedule_work(work_type, Work { handler, ctx }) {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
 }
 
 #[no_mangle]
 pub(crate) extern "C" fn uacpi_kernel_wait_for_work_completion() -> Status {
-    match get_kernel_api().wait_for_work_completion() {
-        Ok(()) => Status::Ok,
-        Err(status) => status,
+    no_unwind(|| {
+        match get_kernel_api().wait_for_work_completion() {
+            Ok(()) => Status::Ok,
+            Err(status) => status,
+        }
+    })
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn count(ctx: Handle) {
+        assert_eq!(ctx.as_u64(), 42);
+        RUNS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn scheduled_work_runs_once() {
+        let api = Arc::new(MockKernelApi::new(0));
+        set_kernel_api(api.clone());
+
+        let status = uacpi_kernel_schedule_work(WorkType::GPEExecution, count, Handle::new(42));
+        assert_eq!(status, Status::Ok);
+        assert_eq!(RUNS.load(Ordering::SeqCst), 0);
+        assert_eq!(uacpi_kernel_wait_for_work_completion(), Status::Ok);
+        assert_eq!(RUNS.load(Ordering::SeqCst), 1);
+
+        // the work was consumed, flushing again doesn't run it a second time
+        api.run_work();
+        assert_eq!(RUNS.load(Ordering::SeqCst), 1);
     }
 }
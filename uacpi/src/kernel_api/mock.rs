@@ -0,0 +1,448 @@
+extern crate std;
+
+use crate::kernel_api::{KernelApi, Work};
+use crate::types::{
+    CpuFlags, EventHandle, FatalAction, FatalError, FirmwareRequest, Handle, InterruptRet, IOAddr,
+    MutexHandle, PCIAddress, PhysAddr, SpinlockHandle, Status, ThreadId, WorkType,
+};
+#[cfg(not(feature = "logging"))]
+use crate::types::LogLevel;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec;
+use core::ffi::c_void;
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A counter that can be waited on, backing the mock mutexes, spinlocks and events.
+struct Counter {
+    value: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl Counter {
+    fn new(value: u64) -> Self {
+        Self { value: Mutex::new(value), changed: Condvar::new() }
+    }
+
+    fn from_handle<'a>(handle: u64) -> &'a Counter {
+        unsafe { &*(handle as *const Counter) }
+    }
+
+    fn into_handle(self) -> u64 {
+        Box::into_raw(Box::new(self)) as u64
+    }
+
+    fn destroy(handle: u64) {
+        drop(unsafe { Box::from_raw(handle as *mut Counter) });
+    }
+
+    /// Waits until the counter is non-zero and decrements it.
+    /// A timeout value of 0xFFFF implies infinite wait.
+    fn take(&self, timeout: u16) -> bool {
+        let value = self.value.lock().unwrap();
+        let mut value = if timeout == 0xFFFF {
+            self.changed.wait_while(value, |value| *value == 0).unwrap()
+        } else {
+            let timeout = Duration::from_millis(timeout.into());
+            self.changed.wait_timeout_while(value, timeout, |value| *value == 0).unwrap().0
+        };
+        if *value == 0 {
+            return false;
+        }
+        *value -= 1;
+        true
+    }
+
+    fn give(&self) {
+        let mut value = self.value.lock().unwrap();
+        *value = value.saturating_add(1);
+        self.changed.notify_one();
+    }
+
+    fn set(&self, new: u64) {
+        *self.value.lock().unwrap() = new;
+    }
+
+    fn get(&self) -> u64 {
+        *self.value.lock().unwrap()
+    }
+}
+
+/// An installed interrupt handler and its irq.
+type InterruptHandler = (u32, Box<dyn Fn() -> InterruptRet>);
+
+/// A KernelApi for running code that uses this crate on the host, e.g. in unit tests.
+///
+/// Physical memory is a zeroed buffer of the size passed to new, starting at address 0,
+/// which tests can fill with tables, e.g. using tables::test::SyntheticTables, and inspect
+/// through memory. IO ports and PCI configuration space are plain maps that read as 0
+/// until written. Interrupt handlers only run when triggered with fire_interrupt,
+/// scheduled work only runs on wait_for_work_completion or run_work.
+pub struct MockKernelApi {
+    memory: Mutex<Box<[u8]>>,
+    io: Mutex<BTreeMap<u64, u8>>,
+    pci: Mutex<HashMap<(PCIAddress, usize), u8>>,
+    rsdp: Option<PhysAddr>,
+    boot: Instant,
+    interrupts: Mutex<BTreeMap<u64, InterruptHandler>>,
+    next_interrupt: Mutex<u64>,
+    work: Mutex<VecDeque<Work>>,
+}
+
+impl MockKernelApi {
+    /// Creates the mock with `memory_size` bytes of zeroed physical memory.
+    pub fn new(memory_size: usize) -> Self {
+        Self {
+            memory: Mutex::new(vec![0; memory_size].into_boxed_slice()),
+            io: Mutex::new(BTreeMap::new()),
+            pci: Mutex::new(HashMap::new()),
+            rsdp: None,
+            boot: Instant::now(),
+            interrupts: Mutex::new(BTreeMap::new()),
+            next_interrupt: Mutex::new(1),
+            work: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Sets the address returned from get_rsdp.
+    pub fn with_rsdp(mut self, rsdp: PhysAddr) -> Self {
+        self.rsdp = Some(rsdp);
+        self
+    }
+
+    /// Locks the physical memory, e.g. to place tables into it.
+    /// Mappings handed out by map aren't covered by the lock, so the memory should
+    /// only be changed while uACPI doesn't use it.
+    pub fn memory(&self) -> MutexGuard<'_, Box<[u8]>> {
+        self.memory.lock().unwrap()
+    }
+
+    /// Returns the range [phys, phys + len] of the physical memory, None if it's out of bounds.
+    fn memory_range(&self, phys: u64, len: usize) -> Option<*mut u8> {
+        let mut memory = self.memory();
+        let start = usize::try_from(phys).ok()?;
+        let end = start.checked_add(len)?;
+        if end > memory.len() {
+            return None;
+        }
+        Some(unsafe { memory.as_mut_ptr().add(start) })
+    }
+
+    /// Returns the value last written to the IO ports [port, port + byte_width].
+    pub fn io_value(&self, port: u64, byte_width: u8) -> u64 {
+        let io = self.io.lock().unwrap();
+        read_bytes(byte_width, |i| io.get(&(port + i as u64)).copied().unwrap_or(0))
+    }
+
+    /// Runs every installed handler for `irq` until one of them handles it.
+    pub fn fire_interrupt(&self, irq: u32) -> InterruptRet {
+        let interrupts = self.interrupts.lock().unwrap();
+        for (handler_irq, handler) in interrupts.values() {
+            if *handler_irq == irq && handler() == InterruptRet::Handled {
+                return InterruptRet::Handled;
+            }
+        }
+        InterruptRet::NotHandled
+    }
+
+    /// Runs all scheduled work, including work scheduled while running it.
+    pub fn run_work(&self) {
+        loop {
+            // the lock is released before running, work may schedule more work
+            let Some(work) = self.work.lock().unwrap().pop_front() else {
+                break;
+            };
+            work.run();
+        }
+    }
+}
+
+/// Assembles a little-endian value of `byte_width` bytes.
+fn read_bytes(byte_width: u8, byte: impl Fn(usize) -> u8) -> u64 {
+    (0..byte_width as usize).fold(0, |value, i| value | (byte(i) as u64) << (i * 8))
+}
+
+/// Splits a value into `byte_width` little-endian bytes.
+fn write_bytes(byte_width: u8, val: u64, mut byte: impl FnMut(usize, u8)) {
+    for i in 0..byte_width as usize {
+        byte(i, (val >> (i * 8)) as u8);
+    }
+}
+
+impl KernelApi for MockKernelApi {
+    fn get_rsdp(&self) -> Result<PhysAddr, Status> {
+        self.rsdp.ok_or(Status::NotFound)
+    }
+
+    unsafe fn raw_memory_read(&self, phys: PhysAddr, byte_width: u8) -> Result<u64, Status> {
+        let ptr = self.memory_range(phys.as_u64(), byte_width.into()).ok_or(Status::InvalidArgument)?;
+        Ok(read_bytes(byte_width, |i| ptr.add(i).read()))
+    }
+
+    unsafe fn raw_memory_write(
+        &self,
+        phys: PhysAddr,
+        byte_width: u8,
+        val: u64,
+    ) -> Result<(), Status> {
+        let ptr = self.memory_range(phys.as_u64(), byte_width.into()).ok_or(Status::InvalidArgument)?;
+        write_bytes(byte_width, val, |i, byte| ptr.add(i).write(byte));
+        Ok(())
+    }
+
+    unsafe fn raw_io_read(&self, addr: IOAddr, byte_width: u8) -> Result<u64, Status> {
+        Ok(self.io_value(addr.as_u64(), byte_width))
+    }
+
+    unsafe fn raw_io_write(&self, addr: IOAddr, byte_width: u8, val: u64) -> Result<(), Status> {
+        let mut io = self.io.lock().unwrap();
+        write_bytes(byte_width, val, |i, byte| {
+            io.insert(addr.as_u64() + i as u64, byte);
+        });
+        Ok(())
+    }
+
+    unsafe fn pci_read(
+        &self,
+        address: PCIAddress,
+        offset: usize,
+        byte_width: u8,
+    ) -> Result<u64, Status> {
+        let pci = self.pci.lock().unwrap();
+        Ok(read_bytes(byte_width, |i| pci.get(&(address, offset + i)).copied().unwrap_or(0)))
+    }
+
+    unsafe fn pci_write(
+        &self,
+        address: PCIAddress,
+        offset: usize,
+        byte_width: u8,
+        val: u64,
+    ) -> Result<(), Status> {
+        let mut pci = self.pci.lock().unwrap();
+        write_bytes(byte_width, val, |i, byte| {
+            pci.insert((address, offset + i), byte);
+        });
+        Ok(())
+    }
+
+    unsafe fn io_map(&self, base: IOAddr, _len: usize) -> Result<Handle, Status> {
+        Ok(Handle::new(base.as_u64()))
+    }
+
+    unsafe fn io_unmap(&self, _handle: Handle) {}
+
+    unsafe fn io_read(&self, handle: Handle, offset: usize, byte_width: u8) -> Result<u64, Status> {
+        self.raw_io_read(IOAddr::try_new(handle.as_u64() + offset as u64)?, byte_width)
+    }
+
+    unsafe fn io_write(
+        &self,
+        handle: Handle,
+        offset: usize,
+        byte_width: u8,
+        val: u64,
+    ) -> Result<(), Status> {
+        self.raw_io_write(IOAddr::try_new(handle.as_u64() + offset as u64)?, byte_width, val)
+    }
+
+    unsafe fn map(&self, phys: PhysAddr, len: usize) -> Result<*mut c_void, Status> {
+        self.memory_range(phys.as_u64(), len).map(|ptr| ptr.cast()).ok_or(Status::MappingFailed)
+    }
+
+    unsafe fn unmap(&self, _addr: *mut c_void, _len: usize) {}
+
+    #[cfg(not(feature = "logging"))]
+    fn log(&self, _log_level: LogLevel, string: &str) {
+        std::eprint!("{string}");
+    }
+
+    fn get_ticks(&self) -> u64 {
+        (self.boot.elapsed().as_nanos() / 100) as u64
+    }
+
+    fn get_nanoseconds_since_boot(&self) -> u64 {
+        self.boot.elapsed().as_nanos() as u64
+    }
+
+    fn stall(&self, usec: u8) {
+        std::thread::sleep(Duration::from_micros(usec.into()));
+    }
+
+    fn sleep(&self, msec: u8) {
+        std::thread::sleep(Duration::from_millis(msec.into()));
+    }
+
+    fn create_mutex(&self) -> MutexHandle {
+        MutexHandle::new(Counter::new(1).into_handle())
+    }
+
+    fn destroy_mutex(&self, mutex: MutexHandle) {
+        Counter::destroy(mutex.as_u64())
+    }
+
+    fn acquire_mutex(&self, mutex: MutexHandle, timeout: u16) -> bool {
+        Counter::from_handle(mutex.as_u64()).take(timeout)
+    }
+
+    fn release_mutex(&self, mutex: MutexHandle) {
+        Counter::from_handle(mutex.as_u64()).give()
+    }
+
+    fn create_spinlock(&self) -> SpinlockHandle {
+        SpinlockHandle::new(Counter::new(1).into_handle())
+    }
+
+    fn destroy_spinlock(&self, lock: SpinlockHandle) {
+        Counter::destroy(lock.as_u64())
+    }
+
+    fn acquire_spinlock(&self, lock: SpinlockHandle) -> CpuFlags {
+        Counter::from_handle(lock.as_u64()).take(0xFFFF);
+        CpuFlags::new(0)
+    }
+
+    fn release_spinlock(&self, lock: SpinlockHandle, _cpu_flags: CpuFlags) {
+        Counter::from_handle(lock.as_u64()).give()
+    }
+
+    fn create_event(&self) -> EventHandle {
+        EventHandle::new(Counter::new(0).into_handle())
+    }
+
+    fn destroy_event(&self, event: EventHandle) {
+        Counter::destroy(event.as_u64())
+    }
+
+    fn wait_for_event(&self, event: EventHandle, timeout: u16) -> bool {
+        Counter::from_handle(event.as_u64()).take(timeout)
+    }
+
+    fn signal_event(&self, event: EventHandle) {
+        Counter::from_handle(event.as_u64()).give()
+    }
+
+    fn reset_event(&self, event: EventHandle) {
+        Counter::from_handle(event.as_u64()).set(0)
+    }
+
+    fn event_count(&self, event: EventHandle) -> Option<u64> {
+        Some(Counter::from_handle(event.as_u64()).get())
+    }
+
+    fn get_thread_id(&self) -> ThreadId {
+        std::thread_local!(static ID: u8 = const { 0 });
+        ThreadId::new(ID.with(|id| id as *const u8 as *mut c_void))
+    }
+
+    fn firmware_request(&self, _req: FirmwareRequest) -> Result<(), Status> {
+        Ok(())
+    }
+
+    fn fatal_error(&self, _error: FatalError) -> FatalAction {
+        FatalAction::Continue
+    }
+
+    fn install_interrupt_handler(&self, irq: u32, handler: Box<dyn Fn() -> InterruptRet>,
+    ) -> Result<Handle, Status> {
+        let mut next = self.next_interrupt.lock().unwrap();
+        let handle = *next;
+        *next += 1;
+        self.interrupts.lock().unwrap().insert(handle, (irq, handler));
+        Ok(Handle::new(handle))
+    }
+
+    fn uninstall_interrupt_handler(&self, handle: Handle) -> Result<(), Status> {
+        match self.interrupts.lock().unwrap().remove(&handle.as_u64()) {
+            Some(_) => Ok(()),
+            None => Err(Status::NotFound),
+        }
+    }
+
+    fn schedule_work(&self, _work_type: WorkType, work: Work) -> Result<(), Status> {
+        self.work.lock().unwrap().push_back(work);
+        Ok(())
+    }
+
+    fn wait_for_work_completion(&self) -> Result<(), Status> {
+        self.run_work();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_map_read_write() {
+        let api = MockKernelApi::new(0x1000);
+        let phys = PhysAddr::new(0x100);
+        unsafe {
+            api.raw_memory_write(phys, 4, 0x1234_5678).unwrap();
+            assert_eq!(api.raw_memory_read(phys, 2).unwrap(), 0x5678);
+
+            let ptr = api.map(phys, 8).unwrap().cast::<u8>();
+            assert_eq!(ptr.read(), 0x78);
+            ptr.add(4).write(0xAB);
+            api.unmap(ptr.cast::<c_void>(), 8);
+
+            assert_eq!(api.memory()[0x104], 0xAB);
+            assert_eq!(api.map(PhysAddr::new(0xFFC), 8), Err(Status::MappingFailed));
+            assert_eq!(api.raw_memory_read(PhysAddr::new(0x1000), 1), Err(Status::InvalidArgument));
+        }
+    }
+
+    #[test]
+    fn mutex_timeout() {
+        let api = MockKernelApi::new(0);
+        let mutex = api.create_mutex();
+        assert!(api.acquire_mutex(mutex, 0));
+        assert!(!api.acquire_mutex(mutex, 1));
+        api.release_mutex(mutex);
+        assert!(api.acquire_mutex(mutex, 1));
+        api.release_mutex(mutex);
+        api.destroy_mutex(mutex);
+    }
+
+    extern "C" fn count(ctx: Handle) {
+        let counter = unsafe { &*(ctx.as_u64() as *const Mutex<u32>) };
+        *counter.lock().unwrap() += 1;
+    }
+
+    #[test]
+    fn schedule_and_run_work() {
+        let api = MockKernelApi::new(0);
+        let counter = Mutex::new(0u32);
+        let ctx = Handle::new(&counter as *const Mutex<u32> as u64);
+        api.schedule_work(WorkType::Notification, Work { handler: count, ctx }).unwrap();
+        assert_eq!(*counter.lock().unwrap(), 0);
+        api.run_work();
+        assert_eq!(*counter.lock().unwrap(), 1);
+        assert!(api.work.lock().unwrap().is_empty());
+    }
+
+    static NESTED_RUNS: Mutex<u32> = Mutex::new(0);
+
+    extern "C" fn schedule_nested(ctx: Handle) {
+        let api = unsafe { &*(ctx.as_u64() as *const MockKernelApi) };
+        let mut runs = NESTED_RUNS.lock().unwrap();
+        *runs += 1;
+        if *runs < 3 {
+            api.schedule_work(WorkType::Notification, Work { handler: schedule_nested, ctx })
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn wait_for_nested_work() {
+        let api = MockKernelApi::new(0);
+        let ctx = Handle::new(&api as *const MockKernelApi as u64);
+        api.schedule_work(WorkType::GPEExecution, Work { handler: schedule_nested, ctx }).unwrap();
+        api.wait_for_work_completion().unwrap();
+        assert_eq!(*NESTED_RUNS.lock().unwrap(), 3);
+        assert!(api.work.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::types::{CpuFlags, EventHandle, MutexHandle, SpinlockHandle, ThreadId};
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Synchronization primitives for kernels that only run uACPI on a single CPU.
+///
+/// Spinlocks are implemented by disabling interrupts, mutexes and events by
+/// polling with interrupts enabled so an interrupt handler can still release
+/// or signal them. Every other part of `KernelApi` is left to the user:
+/// embed this struct in your own type and forward the mutex, spinlock, event
+/// and thread id methods of your `KernelApi` implementation to it.
+///
+/// Timed waits use the stall hook to measure the timeout. Without one a wait with
+/// a timeout fails right away if it can't be satisfied, only infinite waits spin.
+pub struct SingleCoreApi {
+    stall: Option<fn(u8)>,
+}
+
+impl SingleCoreApi {
+    /// Creates the primitives without a stall hook.
+    pub const fn new() -> Self {
+        Self { stall: None }
+    }
+
+    /// Creates the primitives with a hook that spins for the specified amount of microseconds,
+    /// usually the same function that implements `KernelApi::stall`.
+    pub const fn with_stall(stall: fn(u8)) -> Self {
+        Self { stall: Some(stall) }
+    }
+
+    /// Polls `try_take` once per 100 microseconds until it succeeds or `timeout` milliseconds pass.
+    /// A timeout value of 0xFFFF implies infinite wait.
+    fn poll(&self, timeout: u16, try_take: impl Fn() -> bool) -> bool {
+        if try_take() {
+            return true;
+        }
+
+        let Some(stall) = self.stall else {
+            // the timeout can't be measured, so only an infinite wait keeps trying
+            if timeout != 0xFFFF {
+                return false;
+            }
+            loop {
+                core::hint::spin_loop();
+                if try_take() {
+                    return true;
+                }
+            }
+        };
+
+        let mut remaining = u32::from(timeout) * 10;
+        while timeout == 0xFFFF || remaining > 0 {
+            stall(100);
+            if try_take() {
+                return true;
+            }
+            remaining = remaining.saturating_sub(1);
+        }
+        false
+    }
+
+    /// Creates a non-recursive mutex.
+    pub fn create_mutex(&self) -> MutexHandle {
+        MutexHandle::new(Box::into_raw(Box::new(AtomicBool::new(false))) as u64)
+    }
+
+    /// Destroys a mutex previously created by create_mutex.
+    pub fn destroy_mutex(&self, mutex: MutexHandle) {
+        drop(unsafe { Box::from_raw(mutex.as_u64() as *mut AtomicBool) });
+    }
+
+    /// Tries to acquire a mutex with a millisecond timeout.
+    /// A timeout value of 0xFFFF implies infinite wait.
+    pub fn acquire_mutex(&self, mutex: MutexHandle, timeout: u16) -> bool {
+        let locked = unsafe { &*(mutex.as_u64() as *const AtomicBool) };
+        self.poll(timeout, || !locked.swap(true, Ordering::Acquire))
+    }
+
+    /// Releases a previously acquired mutex.
+    pub fn release_mutex(&self, mutex: MutexHandle) {
+        let locked = unsafe { &*(mutex.as_u64() as *const AtomicBool) };
+        locked.store(false, Ordering::Release);
+    }
+
+    /// Creates a spinlock.
+    pub fn create_spinlock(&self) -> SpinlockHandle {
+        SpinlockHandle::new(Box::into_raw(Box::new(AtomicBool::new(false))) as u64)
+    }
+
+    /// Destroys a spinlock previously created by create_spinlock.
+    pub fn destroy_spinlock(&self, lock: SpinlockHandle) {
+        drop(unsafe { Box::from_raw(lock.as_u64() as *mut AtomicBool) });
+    }
+
+    /// Disables interrupts and acquires a spinlock.
+    /// With a single CPU nobody else can hold the lock once interrupts are off,
+    /// so this never spins.
+    pub fn acquire_spinlock(&self, lock: SpinlockHandle) -> CpuFlags {
+        let flags = unsafe { arch::disable_interrupts() };
+        let locked = unsafe { &*(lock.as_u64() as *const AtomicBool) };
+        let was_locked = locked.swap(true, Ordering::Acquire);
+        debug_assert!(!was_locked, "recursive spinlock acquisition");
+        CpuFlags::new(flags)
+    }
+
+    /// Releases a spinlock and restores the previous interrupt state.
+    pub fn release_spinlock(&self, lock: SpinlockHandle, cpu_flags: CpuFlags) {
+        let locked = unsafe { &*(lock.as_u64() as *const AtomicBool) };
+        locked.store(false, Ordering::Release);
+        unsafe { arch::restore_interrupts(cpu_flags.0) }
+    }
+
+    /// Creates a semaphore-like event object.
+    pub fn create_event(&self) -> EventHandle {
+        EventHandle::new(Box::into_raw(Box::new(AtomicU64::new(0))) as u64)
+    }
+
+    /// Destroys an event previously created by create_event.
+    pub fn destroy_event(&self, event: EventHandle) {
+        drop(unsafe { Box::from_raw(event.as_u64() as *mut AtomicU64) });
+    }
+
+    /// Waits for an event (counter > 0) with a millisecond timeout.
+    /// Only an interrupt handler can signal the event while waiting.
+    pub fn wait_for_event(&self, event: EventHandle, timeout: u16) -> bool {
+        let counter = unsafe { &*(event.as_u64() as *const AtomicU64) };
+        self.poll(timeout, || {
+            counter
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |count| count.checked_sub(1))
+                .is_ok()
+        })
+    }
+
+    /// Signals an event by incrementing its internal counter by 1.
+    pub fn signal_event(&self, event: EventHandle) {
+        let counter = unsafe { &*(event.as_u64() as *const AtomicU64) };
+        let _ = counter.fetch_update(Ordering::Release, Ordering::Relaxed, |count| {
+            Some(count.saturating_add(1))
+        });
+    }
+
+    /// Resets an event by setting its internal counter to 0.
+    pub fn reset_event(&self, event: EventHandle) {
+        let counter = unsafe { &*(event.as_u64() as *const AtomicU64) };
+        counter.store(0, Ordering::Release);
+    }
+
+    /// Returns the current value of the internal counter of an event.
+    pub fn event_count(&self, event: EventHandle) -> Option<u64> {
+        let counter = unsafe { &*(event.as_u64() as *const AtomicU64) };
+        Some(counter.load(Ordering::Acquire))
+    }
+
+    /// Returns the identifier of the only thread.
+    pub fn get_thread_id(&self) -> ThreadId {
+        ThreadId::new(core::ptr::NonNull::<u8>::dangling().as_ptr().cast())
+    }
+}
+
+impl Default for SingleCoreApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod arch {
+    use core::arch::asm;
+    use core::ffi::c_ulong;
+
+    const IF: c_ulong = 1 << 9;
+
+    pub unsafe fn disable_interrupts() -> c_ulong {
+        let flags: u64;
+        asm!("pushfq", "pop {}", "cli", out(reg) flags);
+        flags as c_ulong
+    }
+
+    pub unsafe fn restore_interrupts(flags: c_ulong) {
+        if flags & IF != 0 {
+            asm!("sti", options(nostack));
+        }
+    }
+}
+
+#[cfg(target_arch = "x86")]
+mod arch {
+    use core::arch::asm;
+    use core::ffi::c_ulong;
+
+    const IF: c_ulong = 1 << 9;
+
+    pub unsafe fn disable_interrupts() -> c_ulong {
+        let flags: u32;
+        asm!("pushfd", "pop {}", "cli", out(reg) flags);
+        flags as c_ulong
+    }
+
+    pub unsafe fn restore_interrupts(flags: c_ulong) {
+        if flags & IF != 0 {
+            asm!("sti", options(nostack));
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arch {
+    use core::arch::asm;
+    use core::ffi::c_ulong;
+
+    pub unsafe fn disable_interrupts() -> c_ulong {
+        let daif: u64;
+        asm!("mrs {}, daif", "msr daifset, #2", out(reg) daif, options(nostack));
+        daif as c_ulong
+    }
+
+    pub unsafe fn restore_interrupts(flags: c_ulong) {
+        asm!("msr daif, {}", in(reg) flags as u64, options(nostack));
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+mod arch {
+    use core::arch::asm;
+    use core::ffi::c_ulong;
+
+    const SIE: c_ulong = 1 << 1;
+
+    pub unsafe fn disable_interrupts() -> c_ulong {
+        let sstatus: c_ulong;
+        asm!("csrrci {}, sstatus, 2", out(reg) sstatus, options(nostack));
+        sstatus
+    }
+
+    pub unsafe fn restore_interrupts(flags: c_ulong) {
+        if flags & SIE != 0 {
+            asm!("csrsi sstatus, 2", options(nostack));
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "x86",
+    target_arch = "aarch64",
+    target_arch = "riscv64"
+)))]
+compile_error!("the single-core feature does not support this architecture");
@@ -2,19 +2,43 @@
 
 extern crate alloc;
 
+// ACPI tables and AML data are little-endian and uACPI reads them natively,
+// the table accessors here convert from little-endian but that alone isn't enough.
+#[cfg(target_endian = "big")]
+compile_error!("uacpi only supports little-endian targets");
+
+pub mod battery;
+pub mod context;
+pub mod event;
+#[cfg(fuzzing)]
+pub mod fuzz;
 pub mod kernel_api;
 pub mod types;
 pub mod namespace;
+pub mod opregion;
+pub mod osi;
+pub mod pci_access;
+pub mod power;
 pub mod sleep;
+pub mod subsystem;
 pub mod tables;
+pub mod thermal;
 pub mod utils;
 
+use alloc::ffi::CString;
 use alloc::vec::Vec;
 use core::ffi::CStr;
 pub use types::*;
+pub use battery::*;
+pub use event::*;
 pub use namespace::*;
+pub use opregion::*;
+pub use osi::*;
+pub use power::*;
 pub use sleep::*;
+pub use subsystem::*;
 pub use tables::*;
+pub use thermal::*;
 pub use utils::*;
 
 pub use uacpi_sys as sys;
@@ -34,6 +58,13 @@ pub fn init(rsdp: PhysAddr, log_level: LogLevel, no_acpi_mode: bool) -> Result<(
     }
 }
 
+/// Same as init, except the RSDP address is queried from KernelApi::get_rsdp.
+/// The kernel api must be set before calling this.
+pub fn init_auto(log_level: LogLevel, no_acpi_mode: bool) -> Result<(), Status> {
+    let rsdp = kernel_api::get_kernel_api().get_rsdp()?;
+    init(rsdp, log_level, no_acpi_mode)
+}
+
 pub fn namespace_load() -> Result<(), Status> {
     let status: Status = unsafe { uacpi_sys::uacpi_namespace_load().into() };
 
@@ -52,12 +83,224 @@ pub fn namespace_initialize() -> Result<(), Status> {
     }
 }
 
+#[cfg(not(feature = "reduced-hardware"))]
+/// Transitions the hardware into ACPI mode by writing ACPI_ENABLE to SMI_CMD.
+/// init already does this unless `no_acpi_mode` was set, in which case this has to be
+/// called before any SCI, and thus any fixed event or GPE, is delivered.
+/// Call it once the SCI handler is installed, i.e. after namespace_load,
+/// and before enabling fixed events or GPEs that the kernel relies on.
+pub fn enter_acpi_mode() -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe { uacpi_sys::uacpi_enter_acpi_mode().into() };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status),
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Transitions the hardware into ACPI mode by writing ACPI_ENABLE to SMI_CMD.
+/// Reduced hardware systems are always in ACPI mode.
+pub fn enter_acpi_mode() -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Transitions the hardware back into legacy mode by writing ACPI_DISABLE to SMI_CMD.
+/// No SCIs are delivered afterwards.
+pub fn leave_acpi_mode() -> Result<(), Status> {
+    require_full_hardware()?;
+
+    let status: Status = unsafe { uacpi_sys::uacpi_leave_acpi_mode().into() };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status),
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Transitions the hardware back into legacy mode by writing ACPI_DISABLE to SMI_CMD.
+/// Reduced hardware systems are always in ACPI mode.
+pub fn leave_acpi_mode() -> Result<(), Status> {
+    Err(Status::CompiledOut)
+}
+
+/// Returns the version of the uACPI headers the bindings were generated from,
+/// encoded as `major << 16 | minor << 8 | patch`. With the system-uacpi feature this is
+/// the version of the headers in UACPI_INCLUDE_DIR, which must match the linked library.
+pub fn uacpi_version() -> u32 {
+    uacpi_sys::UACPI_MAJOR << 16 | uacpi_sys::UACPI_MINOR << 8 | uacpi_sys::UACPI_PATCH
+}
+
+/// The compile time options uACPI was built with, see build_config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildConfig {
+    /// UACPI_REDUCED_HARDWARE is set, i.e. the reduced-hardware feature is enabled.
+    pub reduced_hardware: bool,
+    /// UACPI_SIZED_FREES is set, which uacpi-sys always does.
+    pub sized_frees: bool,
+    /// A prebuilt uACPI is linked instead of the vendored one, i.e. the system-uacpi feature.
+    pub system_uacpi: bool,
+}
+
+/// Returns the options uacpi-sys passed to the uACPI build and the bindings,
+/// e.g. to include them in a bug report along with uacpi_version.
+pub fn build_config() -> BuildConfig {
+    BuildConfig {
+        reduced_hardware: cfg!(feature = "reduced-hardware"),
+        sized_frees: true,
+        system_uacpi: cfg!(feature = "system-uacpi"),
+    }
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Checks whether the platform is hardware reduced according to the FADT.
+/// The fixed hardware APIs, i.e. the ACPI mode transitions, the waking vector, GPEs
+/// and fixed events, return Unimplemented on such platforms, so the same build can
+/// run on both hardware classes. The reduced-hardware feature is only needed to drop
+/// the fixed hardware support from uACPI altogether.
+pub fn is_hardware_reduced() -> bool {
+    table_fadt().is_ok_and(|fadt| fadt.flags & uacpi_sys::ACPI_HW_REDUCED_ACPI != 0)
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Checks whether the platform is hardware reduced according to the FADT.
+/// Always true with the reduced-hardware feature, uACPI then treats every platform as such.
+pub fn is_hardware_reduced() -> bool {
+    true
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Fails the fixed hardware APIs with Unimplemented on hardware reduced platforms.
+pub(crate) fn require_full_hardware() -> Result<(), Status> {
+    if is_hardware_reduced() {
+        Err(Status::Unimplemented)
+    } else {
+        Ok(())
+    }
+}
+
+/// The frequency of the ACPI PM timer in Hz.
+pub const PM_TIMER_FREQUENCY: u64 = 3_579_545;
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Returns the number of bits of the PM timer, 24 or 32, after which it wraps around.
+/// Returns NotFound if the FADT doesn't describe a PM timer.
+pub fn pm_timer_width() -> Result<u32, Status> {
+    require_full_hardware()?;
+    let fadt = table_fadt()?;
+    if { fadt.x_pm_tmr_blk.address } == 0 {
+        return Err(Status::NotFound);
+    }
+
+    if u32::from_le(fadt.flags) & uacpi_sys::ACPI_TMR_VAL_EXT != 0 {
+        Ok(32)
+    } else {
+        Ok(24)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Returns the number of bits of the PM timer, 24 or 32, after which it wraps around.
+/// Reduced hardware systems have no PM timer.
+pub fn pm_timer_width() -> Result<u32, Status> {
+    Err(Status::CompiledOut)
+}
+
+#[cfg(not(feature = "reduced-hardware"))]
+/// Reads the current count of the PM timer, which runs at PM_TIMER_FREQUENCY and
+/// wraps around after pm_timer_width bits. It's usable without calibration as soon
+/// as the tables are accessible, e.g. to calibrate the TSC or the LAPIC timer.
+pub fn read_pm_timer() -> Result<u32, Status> {
+    let width = pm_timer_width()?;
+    let mut value = 0;
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_read_register(uacpi_sys::UACPI_REGISTER_PM_TMR, &mut value).into()
+    };
+
+    match status {
+        Status::Ok => Ok((value & ((1u64 << width) - 1)) as u32),
+        _ => Err(status)
+    }
+}
+
+#[cfg(feature = "reduced-hardware")]
+/// Reads the current count of the PM timer.
+/// Reduced hardware systems have no PM timer.
+pub fn read_pm_timer() -> Result<u32, Status> {
+    Err(Status::CompiledOut)
+}
+
+/// The held ACPI global lock, released on drop.
+#[must_use = "the global lock is released when the guard is dropped"]
+#[derive(Debug)]
+pub struct GlobalLockGuard {
+    seq: u32,
+}
+
+impl Drop for GlobalLockGuard {
+    fn drop(&mut self) {
+        // the sequence number came from uACPI, so this can't fail
+        let _ = unsafe { uacpi_sys::uacpi_release_global_lock(self.seq) };
+    }
+}
+
+/// Acquires the ACPI global lock, which serializes accesses to hardware shared with the
+/// firmware, e.g. the EC when the ECDT or the _GLK of the EC device asks for it.
+/// `timeout` is in milliseconds, 0xFFFF waits forever. Returns Timeout if the lock
+/// couldn't be acquired in time.
+/// The bit protocol over the FACS lock dword, i.e. setting the pending bit when the firmware
+/// owns the lock, waiting for the global lock event and signaling GBL_RLS on release if the
+/// firmware is pending, is done by uACPI. It also takes the lock for AML fields with the
+/// Lock rule, so holding it also blocks AML that accesses such fields.
+pub fn acquire_global_lock(timeout: u16) -> Result<GlobalLockGuard, Status> {
+    let mut seq = 0;
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_acquire_global_lock(timeout, &mut seq).into()
+    };
+
+    match status {
+        Status::Ok => Ok(GlobalLockGuard { seq }),
+        _ => Err(status)
+    }
+}
+
 pub fn get_current_init_level() -> InitLevel {
     unsafe {
         uacpi_sys::uacpi_get_current_init_level().into()
     }
 }
 
+/// Same as get_current_init_level, but returns InternalError for a level
+/// unknown to this crate instead of treating it as Early.
+pub fn try_get_current_init_level() -> Result<InitLevel, Status> {
+    let level = unsafe { uacpi_sys::uacpi_get_current_init_level() };
+    InitLevel::from_raw(level).ok_or(Status::InternalError)
+}
+
+/// Returns InitLevelMismatch if uACPI has not reached `min` yet,
+/// for use with `?` at the start of functions that depend on the init order.
+pub fn require_init_level(min: InitLevel) -> Result<(), Status> {
+    if get_current_init_level() < min {
+        Err(Status::InitLevelMismatch)
+    } else {
+        Ok(())
+    }
+}
+
+/// Evaluates the object at `path` relative to `parent` with `args`.
+///
+/// eval may be called from several CPUs at once, including on overlapping subtrees.
+/// uACPI serializes internally: the namespace is protected by its own lock and
+/// every Serialized method gets an implicit mutex created through KernelApi::create_mutex,
+/// so concurrent callers of such a method block until the current one returns.
+/// The sync level is tracked per thread, using KernelApi::get_thread_id, and there is no
+/// API to query or change it. AmlSyncLevelTooHigh therefore never results from concurrency
+/// alone, it means the AML itself acquired a mutex or called a Serialized method with a lower
+/// sync level than one it already holds, or get_thread_id returned the same id on different CPUs.
 pub fn eval<'a>(parent: &NamespaceNode, path: &CStr, args: impl IntoIterator<Item=&'a Object>,
 ) -> Result<Object, Status> {
     let mut args_vec: Vec<_> = args.into_iter().map(|obj| obj.0).collect();
@@ -75,3 +318,124 @@ pub fn eval<'a>(parent: &NamespaceNode, path: &CStr, args: impl IntoIterator<Ite
         }
     }
 }
+
+/// Same as eval, but takes the arguments as plain values, see Args.
+pub fn eval_args(parent: &NamespaceNode, path: &CStr, args: &Args) -> Result<Object, Status> {
+    let objects = args.to_objects()?;
+    eval(parent, path, &objects)
+}
+
+/// Same as eval, but with the AML loop timeout set to `seconds` for the duration of the call,
+/// e.g. to bound methods run for untrusted hotplug events more tightly.
+/// uACPI can't abort an evaluation from the outside, the only limit it offers is this
+/// timeout, with a granularity of seconds, which applies to each While loop on its own,
+/// not to the evaluation as a whole, and doesn't cover Sleep, Stall or waiting on a mutex.
+/// The timeout is a global setting, concurrent evaluations see the shorter timeout as well,
+/// and an evaluation that changes it concurrently with this one may have its value lost.
+pub fn eval_with_loop_timeout<'a>(
+    parent: &NamespaceNode,
+    path: &CStr,
+    args: impl IntoIterator<Item=&'a Object>,
+    seconds: u32,
+) -> Result<Object, Status> {
+    let previous = context::get_loop_timeout();
+    context::set_loop_timeout(seconds);
+    let ret = eval(parent, path, args);
+    context::set_loop_timeout(previous);
+    ret
+}
+
+/// Evaluates `path` relative to `parent` and resolves the returned name string,
+/// e.g. the result of _EJD, or reference, e.g. RefOf of a named object,
+/// to the namespace node it refers to.
+/// Names are resolved relative to the device that owns the object at `path`,
+/// references as described by Object::resolve_reference.
+/// Results that are neither, such as an integer, return TypeMismatch.
+pub fn eval_as_node<'a>(parent: &NamespaceNode, path: &CStr, args: impl IntoIterator<Item=&'a Object>,
+) -> Result<NamespaceNode, Status> {
+    let obj = eval(parent, path, args)?;
+    if obj.object_type() == Some(ObjectType::Reference) {
+        return obj.resolve_reference();
+    }
+
+    let node = find_node(parent, path)?;
+    let scope = node.parent().unwrap_or(node);
+    obj.resolve_as_node(&scope)
+}
+
+/// Finds the node at `path` relative to `parent`, without applying the AML search rules.
+pub fn find_node(parent: &NamespaceNode, path: &CStr) -> Result<NamespaceNode, Status> {
+    let mut ret = core::ptr::null_mut();
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_namespace_node_find(parent.0, path.as_ptr(), &mut ret).into()
+    };
+
+    match status {
+        Status::Ok => Ok(NamespaceNode(ret)),
+        _ => Err(status)
+    }
+}
+
+/// Evaluates `path` relative to `parent` without arguments, expecting an integer.
+pub fn eval_simple_integer(parent: &NamespaceNode, path: &CStr) -> Result<u64, Status> {
+    let mut ret = 0;
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_eval_simple_integer(parent.0, path.as_ptr(), &mut ret).into()
+    };
+
+    match status {
+        Status::Ok => Ok(ret),
+        _ => Err(status)
+    }
+}
+
+/// Evaluates `path` relative to `parent` without arguments, expecting a string.
+/// The returned string is a copy, the evaluated object is released.
+pub fn eval_simple_string(parent: &NamespaceNode, path: &CStr) -> Result<CString, Status> {
+    let mut ret = core::ptr::null_mut();
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_eval_simple_string(parent.0, path.as_ptr(), &mut ret).into()
+    };
+
+    match status {
+        Status::Ok => {
+            let obj = Object(ret);
+            obj.get_string().map(CString::from).ok_or(Status::TypeMismatch)
+        }
+        _ => Err(status)
+    }
+}
+
+/// Evaluates `path` relative to `parent` without arguments, expecting a buffer.
+/// The returned bytes are a copy, the evaluated object is released.
+pub fn eval_simple_buffer(parent: &NamespaceNode, path: &CStr) -> Result<Vec<u8>, Status> {
+    let mut ret = core::ptr::null_mut();
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_eval_simple_buffer(parent.0, path.as_ptr(), &mut ret).into()
+    };
+
+    match status {
+        Status::Ok => {
+            let obj = Object(ret);
+            obj.get_buffer().map(<[u8]>::to_vec).ok_or(Status::TypeMismatch)
+        }
+        _ => Err(status)
+    }
+}
+
+/// Evaluates `path` relative to `parent` without arguments, expecting a package.
+/// Returns the elements of the package, each holding its own reference.
+pub fn eval_simple_package(parent: &NamespaceNode, path: &CStr) -> Result<Vec<Object>, Status> {
+    let mut ret = core::ptr::null_mut();
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_eval_simple_package(parent.0, path.as_ptr(), &mut ret).into()
+    };
+
+    match status {
+        Status::Ok => {
+            let obj = Object(ret);
+            obj.get_package().map(Iterator::collect).ok_or(Status::TypeMismatch)
+        }
+        _ => Err(status)
+    }
+}
@@ -1,12 +1,306 @@
+use core::ffi::c_void;
+use core::fmt::{self, Debug, Display};
+use crate::{eval, eval_sta, namespace_initialize, NodeInfo, NsIterDecision, ObjectType, Status, STA_FUNCTIONING, STA_PRESENT};
+
+/// A node of the ACPI namespace.
+///
+/// The namespace changes when a table is loaded or unloaded at runtime, e.g. an SSDT
+/// loaded by the AML Load operator. uACPI has no callback for individual nodes being
+/// created or removed, so a kernel that mirrors the namespace in its own device registry
+/// has to rescan it, e.g. with for_each_child or namespace_initialize_with, after a table
+/// was installed, which set_table_installation_handler reports. Stored nodes must be
+/// checked with is_valid before use, since nodes of unloaded tables become dangling.
 #[repr(transparent)]
+#[derive(Debug)]
 pub struct NamespaceNode(pub(crate) *mut uacpi_sys::uacpi_namespace_node);
 
+// Nodes are only ever accessed through uACPI, which does its own locking,
+// so they can be handed to other CPUs, e.g. along with a queued event.
+unsafe impl Send for NamespaceNode {}
+unsafe impl Sync for NamespaceNode {}
+
 impl NamespaceNode {
     pub fn root() -> Self {
         Self(core::ptr::null_mut())
     }
 
+    /// Wraps a node pointer obtained from uACPI, e.g. through the sys bindings.
+    ///
+    /// # Safety
+    /// `ptr` must point to a namespace node owned by uACPI, a null pointer refers to the root.
     pub unsafe fn from_raw(ptr: *mut uacpi_sys::uacpi_namespace_node) -> Self {
         Self(ptr)
     }
+
+    /// Returns the node pointer, resolving the null root alias to the real root node.
+    pub(crate) fn raw(&self) -> *mut uacpi_sys::uacpi_namespace_node {
+        if self.0.is_null() {
+            unsafe { uacpi_sys::uacpi_namespace_root() }
+        } else {
+            self.0
+        }
+    }
+
+    /// Returns the 4 character name of this node.
+    pub fn name(&self) -> AcpiName {
+        let name = unsafe { uacpi_sys::uacpi_namespace_node_name(self.raw()) };
+        AcpiName(unsafe { name.id }.to_le_bytes())
+    }
+
+    /// Returns the type of the object attached to this node.
+    pub fn object_type(&self) -> Result<ObjectType, Status> {
+        let mut typ = 0;
+        let status: Status = unsafe {
+            uacpi_sys::uacpi_namespace_node_type(self.raw(), &mut typ).into()
+        };
+
+        match status {
+            Status::Ok => ObjectType::from_raw(typ).ok_or(Status::InternalError),
+            _ => Err(status)
+        }
+    }
+
+    /// Returns the parent of this node, None for the root.
+    pub fn parent(&self) -> Option<NamespaceNode> {
+        let parent = unsafe { uacpi_sys::uacpi_namespace_node_parent(self.raw()) };
+        if parent.is_null() {
+            None
+        } else {
+            Some(Self(parent))
+        }
+    }
+
+    /// Checks whether this node was removed from the namespace, e.g. by a table unload,
+    /// while still being referenced.
+    pub fn is_dangling(&self) -> bool {
+        unsafe { uacpi_sys::uacpi_namespace_node_is_dangling(self.raw()) }
+    }
+
+    /// Checks whether this node is still part of the namespace, see is_dangling.
+    /// Nodes obtained before uacpi_state_reset are freed and cannot be checked,
+    /// they must not be used at all.
+    pub fn is_valid(&self) -> bool {
+        !self.is_dangling()
+    }
+
+    /// Same as is_valid, but returns NamespaceNodeDangling for use with `?`
+    /// before operations that would misbehave on a removed node.
+    pub fn ensure_valid(&self) -> Result<(), Status> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(Status::NamespaceNodeDangling)
+        }
+    }
+
+    /// Collects the type, name, _ADR, _HID, _UID, _CID, _CLS, _SxD and _SxW of this node at once,
+    /// which is a lot cheaper than evaluating each of them separately.
+    pub fn info(&self) -> Result<NodeInfo, Status> {
+        let mut ret = core::ptr::null_mut();
+        let status: Status = unsafe {
+            uacpi_sys::uacpi_get_namespace_node_info(self.raw(), &mut ret).into()
+        };
+
+        match status {
+            Status::Ok => Ok(NodeInfo(ret)),
+            _ => Err(status)
+        }
+    }
+
+    /// Calls `cb` for every node below this one, depth first.
+    pub fn for_each_child<F: FnMut(&NamespaceNode) -> NsIterDecision>(
+        &self,
+        mut cb: F
+    ) -> Result<(), Status> {
+        let status: Status = unsafe {
+            uacpi_sys::uacpi_namespace_for_each_node_depth_first(
+                self.raw(),
+                Some(iteration_cb::<F>),
+                &mut cb as *mut F as *mut c_void
+            ).into()
+        };
+
+        match status {
+            Status::Ok => Ok(()),
+            _ => Err(status)
+        }
+    }
+
+    /// Initializes the devices in this subtree the same way namespace_initialize
+    /// does for the whole namespace, e.g. after a hotplug or dock event.
+    /// Every device, processor and thermal zone reported present by _STA has its
+    /// _INI run, children of devices that are neither present nor functioning are skipped.
+    /// All devices are visited even if an _INI fails, the first failure is returned.
+    pub fn initialize_subtree(&self) -> Result<(), Status> {
+        let mut result = Ok(());
+
+        match initialize_node(self, &mut result) {
+            NsIterDecision::Continue => {
+                self.for_each_child(|node| initialize_node(node, &mut result))?;
+            }
+            _ => return result,
+        }
+
+        result
+    }
+}
+
+/// Runs _INI for a single node if it's present, returning whether to descend into its children.
+fn initialize_node(node: &NamespaceNode, result: &mut Result<(), Status>) -> NsIterDecision {
+    visit_present_device(node, |_| {
+        match eval(node, c"_INI", []) {
+            Ok(_) | Err(Status::NotFound) => {}
+            Err(status) => {
+                if result.is_ok() {
+                    *result = Err(status);
+                }
+            }
+        }
+    })
+}
+
+/// Calls `f` with the _STA value if `node` is a present device, processor or thermal zone,
+/// returning whether to descend into its children the way namespace_initialize does.
+fn visit_present_device(node: &NamespaceNode, f: impl FnOnce(u32)) -> NsIterDecision {
+    match node.object_type() {
+        Ok(ObjectType::Device | ObjectType::Processor | ObjectType::ThermalZone) => {}
+        _ => return NsIterDecision::Continue,
+    }
+
+    let sta = match eval_sta(node) {
+        Ok(sta) => sta,
+        Err(_) => return NsIterDecision::NextPeer,
+    };
+
+    if sta & STA_PRESENT == 0 {
+        // a device may be absent but still have functioning children, e.g. a dock
+        return if sta & STA_FUNCTIONING != 0 {
+            NsIterDecision::Continue
+        } else {
+            NsIterDecision::NextPeer
+        };
+    }
+
+    f(sta);
+    NsIterDecision::Continue
+}
+
+/// The state namespace_initialize left a present device in, see namespace_initialize_with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+    /// The device is present and functioning and had its _INI run, drivers may bind to it.
+    Initialized,
+    /// The device is present and had its _INI run, but _STA reports it's not functioning,
+    /// e.g. because it failed its self test. Drivers should not bind to it.
+    NotFunctioning,
+}
+
+/// Runs namespace_initialize, then calls `cb` for every present device, processor and
+/// thermal zone in depth first order, i.e. every parent is reported before its children.
+/// uACPI has no per device initialization callback, so this is a second walk that
+/// mirrors the rules namespace_initialize uses to decide which _INI methods to run.
+/// uACPI doesn't report individual _INI failures, they are only logged.
+pub fn namespace_initialize_with<F: FnMut(&NamespaceNode, DeviceState)>(
+    mut cb: F
+) -> Result<(), Status> {
+    namespace_initialize()?;
+
+    NamespaceNode::root().for_each_child(|node| {
+        visit_present_device(node, |sta| {
+            let state = if sta & STA_FUNCTIONING != 0 {
+                DeviceState::Initialized
+            } else {
+                DeviceState::NotFunctioning
+            };
+            cb(node, state)
+        })
+    })
+}
+
+/// Trampoline for the uACPI namespace iteration callbacks,
+/// `user` must point to the `F` that is called for every node.
+pub(crate) unsafe extern "C" fn iteration_cb<F: FnMut(&NamespaceNode) -> NsIterDecision>(
+    user: *mut c_void,
+    node: *mut uacpi_sys::uacpi_namespace_node
+) -> uacpi_sys::uacpi_ns_iteration_decision {
+    // uACPI never passes the root to the callback, so a null node would be misread as the root
+    debug_assert!(!node.is_null(), "uACPI passed a null namespace node");
+    let f = &mut *(user as *mut F);
+    crate::kernel_api::no_unwind(|| f(&NamespaceNode(node))) as _
+}
+
+/// A 4 character ACPI name segment, such as `_SB_` or `PCI0`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AcpiName(pub [u8; 4]);
+
+impl AcpiName {
+    /// Returns the name as a string, "????" if it is not valid ASCII.
+    pub fn as_str(&self) -> &str {
+        match core::str::from_utf8(&self.0) {
+            Ok(name) if self.0.is_ascii() => name,
+            _ => "????",
+        }
+    }
+}
+
+impl Display for AcpiName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Debug for AcpiName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AcpiName({})", self.as_str())
+    }
+}
+
+/// Nodes nested deeper than this are not printed by dump_namespace.
+pub const DUMP_MAX_DEPTH: usize = 64;
+
+/// Returns how many levels below the root `node` is.
+fn node_depth(node: &NamespaceNode) -> usize {
+    let mut depth = 0;
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        depth += 1;
+        if depth > DUMP_MAX_DEPTH {
+            break;
+        }
+        current = parent.parent();
+    }
+    depth
+}
+
+/// Writes the whole namespace tree to `writer`, one node per line,
+/// indented by depth and annotated with the object type.
+/// Dangling nodes are printed without descending into them and
+/// subtrees deeper than DUMP_MAX_DEPTH are elided.
+/// A failing writer stops the dump early without an error.
+pub fn dump_namespace(writer: &mut dyn fmt::Write) -> Result<(), Status> {
+    if writeln!(writer, "\\").is_err() {
+        return Ok(());
+    }
+
+    NamespaceNode::root().for_each_child(|node| {
+        let depth = node_depth(node);
+        let indent = depth * 2;
+
+        let res = if depth > DUMP_MAX_DEPTH {
+            writeln!(writer, "{:indent$}...", "")
+        } else if node.is_dangling() {
+            writeln!(writer, "{:indent$}{} <dangling>", "", node.name())
+        } else {
+            match node.object_type() {
+                Ok(typ) => writeln!(writer, "{:indent$}{} {:?}", "", node.name(), typ),
+                Err(status) => writeln!(writer, "{:indent$}{} <{:?}>", "", node.name(), status),
+            }
+        };
+
+        match res {
+            Err(_) => NsIterDecision::Break,
+            Ok(()) if depth > DUMP_MAX_DEPTH || node.is_dangling() => NsIterDecision::NextPeer,
+            Ok(()) => NsIterDecision::Continue,
+        }
+    })
 }
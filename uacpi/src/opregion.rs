@@ -0,0 +1,277 @@
+use alloc::boxed::Box;
+use core::any::Any;
+use core::ffi::c_void;
+use core::slice;
+use crate::kernel_api::{get_kernel_api, is_valid_width, no_unwind};
+use crate::{IOAddr, NamespaceNode, PhysAddr, Status};
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSpace {
+    SystemMemory = uacpi_sys::UACPI_ADDRESS_SPACE_SYSTEM_MEMORY,
+    SystemIo = uacpi_sys::UACPI_ADDRESS_SPACE_SYSTEM_IO,
+    PciConfig = uacpi_sys::UACPI_ADDRESS_SPACE_PCI_CONFIG,
+    EmbeddedController = uacpi_sys::UACPI_ADDRESS_SPACE_EMBEDDED_CONTROLLER,
+    SmBus = uacpi_sys::UACPI_ADDRESS_SPACE_SMBUS,
+    SystemCmos = uacpi_sys::UACPI_ADDRESS_SPACE_SYSTEM_CMOS,
+    PciBarTarget = uacpi_sys::UACPI_ADDRESS_SPACE_PCI_BAR_TARGET,
+    Ipmi = uacpi_sys::UACPI_ADDRESS_SPACE_IPMI,
+    GeneralPurposeIo = uacpi_sys::UACPI_ADDRESS_SPACE_GENERAL_PURPOSE_IO,
+    GenericSerialBus = uacpi_sys::UACPI_ADDRESS_SPACE_GENERIC_SERIAL_BUS,
+    Pcc = uacpi_sys::UACPI_ADDRESS_SPACE_PCC,
+    Prm = uacpi_sys::UACPI_ADDRESS_SPACE_PRM,
+    FfixedHw = uacpi_sys::UACPI_ADDRESS_SPACE_FFIXEDHW,
+}
+
+impl AddressSpace {
+    pub(crate) fn from_raw(space: uacpi_sys::uacpi_address_space) -> Option<Self> {
+        Some(match space {
+            uacpi_sys::UACPI_ADDRESS_SPACE_SYSTEM_MEMORY => Self::SystemMemory,
+            uacpi_sys::UACPI_ADDRESS_SPACE_SYSTEM_IO => Self::SystemIo,
+            uacpi_sys::UACPI_ADDRESS_SPACE_PCI_CONFIG => Self::PciConfig,
+            uacpi_sys::UACPI_ADDRESS_SPACE_EMBEDDED_CONTROLLER => Self::EmbeddedController,
+            uacpi_sys::UACPI_ADDRESS_SPACE_SMBUS => Self::SmBus,
+            uacpi_sys::UACPI_ADDRESS_SPACE_SYSTEM_CMOS => Self::SystemCmos,
+            uacpi_sys::UACPI_ADDRESS_SPACE_PCI_BAR_TARGET => Self::PciBarTarget,
+            uacpi_sys::UACPI_ADDRESS_SPACE_IPMI => Self::Ipmi,
+            uacpi_sys::UACPI_ADDRESS_SPACE_GENERAL_PURPOSE_IO => Self::GeneralPurposeIo,
+            uacpi_sys::UACPI_ADDRESS_SPACE_GENERIC_SERIAL_BUS => Self::GenericSerialBus,
+            uacpi_sys::UACPI_ADDRESS_SPACE_PCC => Self::Pcc,
+            uacpi_sys::UACPI_ADDRESS_SPACE_PRM => Self::Prm,
+            uacpi_sys::UACPI_ADDRESS_SPACE_FFIXEDHW => Self::FfixedHw,
+            _ => return None,
+        })
+    }
+}
+
+/// Handles the accesses AML makes to operation regions of an address space.
+pub trait RegionHandler {
+    /// Called when an operation region starts using this handler.
+    fn attach(&self, _region: &NamespaceNode) -> Result<(), Status> {
+        Ok(())
+    }
+    /// Reads a value of the specified byte width (1, 2, 4 or 8).
+    /// `address` is absolute for SystemMemory and SystemIO regions
+    /// and an offset into the region otherwise.
+    fn read(&self, address: u64, byte_width: u8) -> Result<u64, Status>;
+    /// Writes a value of the specified byte width (1, 2, 4 or 8).
+    /// `address` is absolute for SystemMemory and SystemIO regions
+    /// and an offset into the region otherwise.
+    fn write(&self, address: u64, byte_width: u8, value: u64) -> Result<(), Status>;
+    /// Called when an operation region stops using this handler.
+    fn detach(&self, _region: &NamespaceNode) {}
+}
+
+/// An installed address space handler, uninstalled on drop.
+pub struct AddressSpaceHandler {
+    node: *mut uacpi_sys::uacpi_namespace_node,
+    space: AddressSpace,
+    _context: Box<dyn Any>,
+}
+
+impl AddressSpaceHandler {
+    pub fn space(&self) -> AddressSpace {
+        self.space
+    }
+
+    /// Installs `callback` with `context` as its handler context.
+    /// `context` must stay valid until the handler is uninstalled,
+    /// which is guaranteed by keeping its box in the returned guard.
+    unsafe fn install<T: 'static>(
+        node: &NamespaceNode,
+        space: AddressSpace,
+        callback: uacpi_sys::uacpi_region_handler,
+        context: Box<T>,
+    ) -> Result<Self, Status> {
+        let ctx = &*context as *const T as *mut c_void;
+        let status: Status = uacpi_sys::uacpi_install_address_space_handler(
+            node.0,
+            space as _,
+            callback,
+            ctx
+        ).into();
+
+        match status {
+            Status::Ok => Ok(Self { node: node.0, space, _context: context }),
+            _ => Err(status)
+        }
+    }
+}
+
+impl Drop for AddressSpaceHandler {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = uacpi_sys::uacpi_uninstall_address_space_handler(self.node, self.space as _);
+        }
+    }
+}
+
+unsafe extern "C" fn region_handler_cb<H: RegionHandler>(
+    op: uacpi_sys::uacpi_region_op,
+    op_data: uacpi_sys::uacpi_handle
+) -> uacpi_sys::uacpi_status {
+    let status = no_unwind(|| match op {
+        uacpi_sys::UACPI_REGION_OP_ATTACH => {
+            let data = op_data as *mut uacpi_sys::uacpi_region_attach_data;
+            let handler = &*((*data).handler_context as *const H);
+            handler.attach(&NamespaceNode((*data).region_node)).err().unwrap_or(Status::Ok)
+        }
+        uacpi_sys::UACPI_REGION_OP_READ => {
+            let data = op_data as *mut uacpi_sys::uacpi_region_rw_data;
+            let handler = &*((*data).handler_context as *const H);
+            match handler.read((*data).__bindgen_anon_1.offset, (*data).byte_width) {
+                Ok(value) => {
+                    (*data).value = value;
+                    Status::Ok
+                }
+                Err(status) => status,
+            }
+        }
+        uacpi_sys::UACPI_REGION_OP_WRITE => {
+            let data = op_data as *mut uacpi_sys::uacpi_region_rw_data;
+            let handler = &*((*data).handler_context as *const H);
+            handler.write((*data).__bindgen_anon_1.offset, (*data).byte_width, (*data).value)
+                .err()
+                .unwrap_or(Status::Ok)
+        }
+        uacpi_sys::UACPI_REGION_OP_DETACH => {
+            let data = op_data as *mut uacpi_sys::uacpi_region_detach_data;
+            let handler = &*((*data).handler_context as *const H);
+            handler.detach(&NamespaceNode((*data).region_node));
+            Status::Ok
+        }
+        _ => Status::InvalidArgument,
+    });
+    status as _
+}
+
+/// Installs `handler` for all operation regions of `space` under `node`.
+/// The handler stays installed until the returned guard is dropped.
+///
+/// This can also be used to work around broken AML by shadowing parts of SystemMemory
+/// or SystemIO for the regions of a single device: uACPI's default handlers for those
+/// are installed at the root, the handler closest to a region wins, and the handler
+/// can pass every access it doesn't override on to default_region_read and default_region_write.
+/// uACPI calls the handler from whichever thread runs the AML accessing a region.
+pub fn install_address_space_handler<H: RegionHandler + Send + Sync + 'static>(
+    node: &NamespaceNode,
+    space: AddressSpace,
+    handler: H
+) -> Result<AddressSpaceHandler, Status> {
+    unsafe {
+        AddressSpaceHandler::install(node, space, Some(region_handler_cb::<H>), Box::new(handler))
+    }
+}
+
+/// Reads SystemMemory or SystemIO directly through the kernel api, the same accesses
+/// uACPI's default handlers make. Meant for handlers that only override part of a space,
+/// `address` is the absolute address passed to RegionHandler::read.
+pub fn default_region_read(space: AddressSpace, address: u64, byte_width: u8) -> Result<u64, Status> {
+    let api = get_kernel_api();
+    match space {
+        AddressSpace::SystemMemory if is_valid_width(byte_width, 8) => unsafe {
+            api.raw_memory_read(PhysAddr::try_new(address)?, byte_width)
+        },
+        AddressSpace::SystemIo if is_valid_width(byte_width, 4) => unsafe {
+            api.raw_io_read(IOAddr::try_new(address)?, byte_width)
+        },
+        _ => Err(Status::InvalidArgument),
+    }
+}
+
+/// Writes SystemMemory or SystemIO directly through the kernel api, see default_region_read.
+pub fn default_region_write(
+    space: AddressSpace,
+    address: u64,
+    byte_width: u8,
+    value: u64
+) -> Result<(), Status> {
+    let api = get_kernel_api();
+    match space {
+        AddressSpace::SystemMemory if is_valid_width(byte_width, 8) => unsafe {
+            api.raw_memory_write(PhysAddr::try_new(address)?, byte_width, value)
+        },
+        AddressSpace::SystemIo if is_valid_width(byte_width, 4) => unsafe {
+            api.raw_io_write(IOAddr::try_new(address)?, byte_width, value)
+        },
+        _ => Err(Status::InvalidArgument),
+    }
+}
+
+/// A view of the shared memory of a generic PCC subspace as seen by AML.
+/// The operation region covers the whole shared memory region,
+/// starting with the signature, command and status fields.
+pub struct PccBuffer<'a>(&'a mut [u8]);
+
+impl<'a> PccBuffer<'a> {
+    /// The size of the signature, command and status header.
+    pub const HEADER_SIZE: usize = 8;
+
+    pub fn signature(&self) -> Option<u32> {
+        let bytes = self.0.get(0..4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn command(&self) -> Option<u16> {
+        let bytes = self.0.get(4..6)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        let bytes = self.0.get(6..8)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Returns the whole shared memory contents including the header.
+    pub fn as_bytes(&mut self) -> &mut [u8] {
+        self.0
+    }
+
+    /// Returns the communication space following the header.
+    pub fn payload(&mut self) -> &mut [u8] {
+        self.0.get_mut(Self::HEADER_SIZE..).unwrap_or_default()
+    }
+}
+
+unsafe extern "C" fn pcc_handler_cb<F: Fn(u8, &mut PccBuffer) -> Result<(), Status>>(
+    op: uacpi_sys::uacpi_region_op,
+    op_data: uacpi_sys::uacpi_handle
+) -> uacpi_sys::uacpi_status {
+    let status = no_unwind(|| match op {
+        uacpi_sys::UACPI_REGION_OP_ATTACH => {
+            // remember the subspace so the send op can tell which channel to ring
+            let data = op_data as *mut uacpi_sys::uacpi_region_attach_data;
+            let subspace = (*data).__bindgen_anon_1.pcc_info.subspace_id;
+            (*data).out_region_context = subspace as usize as *mut c_void;
+            Status::Ok
+        }
+        uacpi_sys::UACPI_REGION_OP_PCC_SEND => {
+            let data = op_data as *mut uacpi_sys::uacpi_region_pcc_send_data;
+            let handler = &*((*data).handler_context as *const F);
+            let subspace = (*data).region_context as usize as u8;
+            let bytes = slice::from_raw_parts_mut(
+                (*data).buffer.__bindgen_anon_1.bytes,
+                (*data).buffer.length
+            );
+            handler(subspace, &mut PccBuffer(bytes)).err().unwrap_or(Status::Ok)
+        }
+        uacpi_sys::UACPI_REGION_OP_DETACH => Status::Ok,
+        _ => Status::InvalidArgument,
+    });
+    status as _
+}
+
+/// Installs a handler for the PCC (Platform Communication Channel) address space under `node`.
+/// uACPI buffers AML accesses to a PCC region and calls `handler` with the subspace index
+/// and the buffered shared memory once AML writes the command field.
+/// The handler must copy the buffer to the subspace shared memory, ring the doorbell
+/// described by the PCCT, wait for the command to complete and copy the response back.
+/// uACPI calls the handler from whichever thread runs the AML accessing the region.
+pub fn install_pcc_handler<F: Fn(u8, &mut PccBuffer) -> Result<(), Status> + Send + Sync + 'static>(
+    node: &NamespaceNode,
+    handler: F
+) -> Result<AddressSpaceHandler, Status> {
+    unsafe {
+        AddressSpaceHandler::install(node, AddressSpace::Pcc, Some(pcc_handler_cb::<F>), Box::new(handler))
+    }
+}
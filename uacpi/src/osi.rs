@@ -0,0 +1,86 @@
+use core::ffi::{c_char, CStr};
+use crate::kernel_api::no_unwind;
+use crate::Status;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceKind {
+    /// Vendor interfaces, e.g. "Windows 2015".
+    Vendor = uacpi_sys::UACPI_INTERFACE_KIND_VENDOR,
+    /// Feature interfaces, e.g. "Module Device".
+    Feature = uacpi_sys::UACPI_INTERFACE_KIND_FEATURE,
+    All = uacpi_sys::UACPI_INTERFACE_KIND_ALL,
+}
+
+/// Installs a new _OSI interface string, so _OSI(name) returns true.
+/// Installing an interface that is known but disabled enables it again.
+pub fn install_interface(name: &CStr, kind: InterfaceKind) -> Result<(), Status> {
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_install_interface(name.as_ptr(), kind as _).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+/// Uninstalls an _OSI interface string, so _OSI(name) returns false.
+/// Built-in interfaces are only disabled and can be installed again later.
+pub fn uninstall_interface(name: &CStr) -> Result<(), Status> {
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_uninstall_interface(name.as_ptr()).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+/// Enables or disables all interfaces of the given kind at once,
+/// e.g. to disable all vendor strings before installing the one Windows version to masquerade as.
+pub fn configure_interfaces(enable: bool, kind: InterfaceKind) -> Result<(), Status> {
+    let action = if enable {
+        uacpi_sys::UACPI_INTERFACE_ACTION_ENABLE
+    } else {
+        uacpi_sys::UACPI_INTERFACE_ACTION_DISABLE
+    };
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_bulk_configure_interfaces(action, kind as _).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
+}
+
+static mut INTERFACE_QUERY_HANDLER: Option<fn(&CStr, bool) -> bool> = None;
+
+unsafe extern "C" fn interface_query_cb(name: *const c_char, supported: bool) -> bool {
+    no_unwind(|| match INTERFACE_QUERY_HANDLER {
+        Some(handler) => handler(CStr::from_ptr(name), supported),
+        None => supported,
+    })
+}
+
+/// Sets a handler that is called on every _OSI query with the queried string and
+/// whether uACPI supports it. The value returned by the handler is returned to AML.
+/// Passing None removes the handler.
+pub fn set_interface_query_handler(handler: Option<fn(&CStr, bool) -> bool>) -> Result<(), Status> {
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_set_interface_query_handler(
+            handler.map(|_| interface_query_cb as _)
+        ).into()
+    };
+
+    match status {
+        Status::Ok => {
+            // keep the old handler if uACPI refused the new one
+            unsafe { INTERFACE_QUERY_HANDLER = handler };
+            Ok(())
+        }
+        _ => Err(status)
+    }
+}
@@ -0,0 +1,99 @@
+//! Helpers for KernelApi::pci_read and pci_write implementations that can only
+//! access the configuration space a dword at a time, e.g. through port IO at 0xCF8.
+//! The implementation accesses the dword at aligned_offset and converts it with these,
+//! accesses that would cross a dword are rejected.
+
+use crate::Status;
+
+/// Returns the offset of the dword that contains `offset`.
+pub fn aligned_offset(offset: usize) -> usize {
+    offset & !0b11
+}
+
+/// Returns the shift of the access within its dword and the mask of its width,
+/// InvalidArgument if the width is invalid or the access crosses the dword.
+fn shift_and_mask(offset: usize, byte_width: u8) -> Result<(u32, u32), Status> {
+    let mask = match byte_width {
+        1 => 0xFF,
+        2 => 0xFFFF,
+        4 => 0xFFFF_FFFF,
+        _ => return Err(Status::InvalidArgument),
+    };
+    let byte = offset & 0b11;
+    if byte + byte_width as usize > 4 {
+        return Err(Status::InvalidArgument);
+    }
+    Ok((byte as u32 * 8, mask))
+}
+
+/// Extracts the value of a `byte_width` access at `offset` from the dword read at
+/// aligned_offset(offset).
+pub fn masked_read(aligned_dword: u32, offset: usize, byte_width: u8) -> Result<u64, Status> {
+    let (shift, mask) = shift_and_mask(offset, byte_width)?;
+    Ok(((aligned_dword >> shift) & mask) as u64)
+}
+
+/// Merges `value` of a `byte_width` access at `offset` into the dword read at
+/// aligned_offset(offset), returning the dword to write back.
+/// Bits of `value` beyond `byte_width` are ignored.
+pub fn masked_write(
+    aligned_dword: u32,
+    offset: usize,
+    byte_width: u8,
+    value: u64,
+) -> Result<u32, Status> {
+    let (shift, mask) = shift_and_mask(offset, byte_width)?;
+    let value = value as u32 & mask;
+    Ok((aligned_dword & !(mask << shift)) | (value << shift))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DWORD: u32 = 0x4433_2211;
+
+    #[test]
+    fn read_within_dword() {
+        for offset in 0..4 {
+            let byte = 0x11 * (offset as u64 + 1);
+            assert_eq!(masked_read(DWORD, 0x40 + offset, 1), Ok(byte));
+        }
+        assert_eq!(masked_read(DWORD, 0x40, 2), Ok(0x2211));
+        assert_eq!(masked_read(DWORD, 0x41, 2), Ok(0x3322));
+        assert_eq!(masked_read(DWORD, 0x42, 2), Ok(0x4433));
+        assert_eq!(masked_read(DWORD, 0x40, 4), Ok(0x4433_2211));
+    }
+
+    #[test]
+    fn write_within_dword() {
+        for offset in 0..4 {
+            let shift = offset * 8;
+            let expected = (DWORD & !(0xFF << shift)) | (0xAA << shift);
+            assert_eq!(masked_write(DWORD, 0x40 + offset, 1, 0x1AA), Ok(expected));
+        }
+        assert_eq!(masked_write(DWORD, 0x40, 2, 0xBBAA), Ok(0x4433_BBAA));
+        assert_eq!(masked_write(DWORD, 0x41, 2, 0xBBAA), Ok(0x44BB_AA11));
+        assert_eq!(masked_write(DWORD, 0x42, 2, 0xBBAA), Ok(0xBBAA_2211));
+        assert_eq!(masked_write(DWORD, 0x40, 4, 0x1_DDCC_BBAA), Ok(0xDDCC_BBAA));
+    }
+
+    #[test]
+    fn reject_dword_crossing() {
+        assert_eq!(masked_read(DWORD, 0x43, 2), Err(Status::InvalidArgument));
+        for offset in 1..4 {
+            assert_eq!(masked_read(DWORD, 0x40 + offset, 4), Err(Status::InvalidArgument));
+            assert_eq!(masked_write(DWORD, 0x40 + offset, 4, 0), Err(Status::InvalidArgument));
+        }
+        assert_eq!(masked_write(DWORD, 0x43, 2, 0), Err(Status::InvalidArgument));
+        assert_eq!(masked_read(DWORD, 0x40, 3), Err(Status::InvalidArgument));
+        assert_eq!(masked_write(DWORD, 0x40, 8, 0), Err(Status::InvalidArgument));
+    }
+
+    #[test]
+    fn aligned_offsets() {
+        for offset in 0x40..0x44 {
+            assert_eq!(aligned_offset(offset), 0x40);
+        }
+    }
+}
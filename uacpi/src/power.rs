@@ -0,0 +1,340 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::ffi::CStr;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU64, Ordering};
+use crate::kernel_api::get_kernel_api;
+use crate::{eval, eval_simple_integer, MutexHandle, NamespaceNode, Status, STA_PRESENT};
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DevicePowerState {
+    D0 = 0,
+    D1 = 1,
+    D2 = 2,
+    D3Hot = 3,
+    D3Cold = 4,
+}
+
+impl DevicePowerState {
+    fn ps_method(self) -> &'static CStr {
+        match self {
+            DevicePowerState::D0 => c"_PS0",
+            DevicePowerState::D1 => c"_PS1",
+            DevicePowerState::D2 => c"_PS2",
+            DevicePowerState::D3Hot | DevicePowerState::D3Cold => c"_PS3",
+        }
+    }
+
+    /// D3cold has no _PRx, the device is in D3cold once all of its resources are off.
+    fn pr_method(self) -> Option<&'static CStr> {
+        match self {
+            DevicePowerState::D0 => Some(c"_PR0"),
+            DevicePowerState::D1 => Some(c"_PR1"),
+            DevicePowerState::D2 => Some(c"_PR2"),
+            DevicePowerState::D3Hot => Some(c"_PR3"),
+            DevicePowerState::D3Cold => None,
+        }
+    }
+}
+
+struct Bookkeeping {
+    /// How many devices currently depend on each power resource, keyed by node.
+    resources: BTreeMap<usize, u32>,
+    /// The last state set with set_device_power_state for each device, keyed by node.
+    devices: BTreeMap<usize, DevicePowerState>,
+}
+
+impl Bookkeeping {
+    /// Turns the power resource on if it's the first device that depends on it.
+    fn reference(&mut self, resource: &NamespaceNode) -> Result<(), Status> {
+        let count = self.resources.entry(resource.raw() as usize).or_insert(0);
+        if *count == 0 {
+            eval(resource, c"_ON", [])?;
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Turns the power resource off if it was the last device that depended on it.
+    fn release(&mut self, resource: &NamespaceNode) -> Result<(), Status> {
+        let key = resource.raw() as usize;
+        match self.resources.get_mut(&key) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                Ok(())
+            }
+            Some(_) => {
+                self.resources.remove(&key);
+                eval(resource, c"_OFF", []).map(|_| ())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// The power resource bookkeeping, serialized with a kernel mutex since _ON and _OFF may sleep.
+struct PowerLock {
+    mutex: AtomicU64,
+    data: UnsafeCell<Bookkeeping>,
+}
+
+// The data is only accessed through a guard holding the mutex.
+unsafe impl Sync for PowerLock {}
+
+static POWER: PowerLock = PowerLock {
+    mutex: AtomicU64::new(0),
+    data: UnsafeCell::new(Bookkeeping {
+        resources: BTreeMap::new(),
+        devices: BTreeMap::new(),
+    }),
+};
+
+struct PowerGuard<'a> {
+    lock: &'a PowerLock,
+    mutex: MutexHandle,
+}
+
+impl PowerLock {
+    fn lock(&self) -> Result<PowerGuard<'_>, Status> {
+        let api = get_kernel_api();
+        let mut raw = self.mutex.load(Ordering::Acquire);
+        if raw == 0 {
+            let mutex = api.create_mutex();
+            match self.mutex.compare_exchange(0, mutex.as_u64(), Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => raw = mutex.as_u64(),
+                Err(existing) => {
+                    api.destroy_mutex(mutex);
+                    raw = existing;
+                }
+            }
+        }
+
+        let mutex = MutexHandle::new(raw);
+        if !api.acquire_mutex(mutex, 0xFFFF) {
+            return Err(Status::InternalError);
+        }
+        Ok(PowerGuard { lock: self, mutex })
+    }
+}
+
+impl Deref for PowerGuard<'_> {
+    type Target = Bookkeeping;
+
+    fn deref(&self) -> &Bookkeeping {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl DerefMut for PowerGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Bookkeeping {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl Drop for PowerGuard<'_> {
+    fn drop(&mut self) {
+        get_kernel_api().release_mutex(self.mutex);
+    }
+}
+
+/// Evaluates the _PRx of `state` and resolves the referenced power resources.
+/// Returns None if the device doesn't have the method.
+fn power_resources(
+    device: &NamespaceNode,
+    state: DevicePowerState
+) -> Result<Option<Vec<NamespaceNode>>, Status> {
+    let Some(method) = state.pr_method() else {
+        return Ok(Some(Vec::new()));
+    };
+
+    let package = match eval(device, method, []) {
+        Ok(package) => package,
+        Err(Status::NotFound) => return Ok(None),
+        Err(status) => return Err(status),
+    };
+
+    package.get_package()
+        .ok_or(Status::TypeMismatch)?
+        .map(|obj| obj.resolve_as_node(device))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Transitions `device` into `state` by turning on the power resources listed in its _PRx,
+/// evaluating its _PSx and turning off the power resources only needed by the previous state.
+/// Power resources are reference counted across all devices, a resource shared by several
+/// devices is turned off once the last of them stops depending on it.
+/// Resources the firmware turned on before the first transition of a device are not
+/// accounted for, so they are only turned off once a device referenced them through this.
+/// Returns NotFound if the device has neither _PSx nor _PRx for D1 or D2.
+/// The parent device must already be in D0, this is not checked.
+pub fn set_device_power_state(device: &NamespaceNode, state: DevicePowerState) -> Result<(), Status> {
+    let key = device.raw() as usize;
+
+    loop {
+        let old = POWER.lock()?.devices.get(&key).copied();
+        if old == Some(state) {
+            return Ok(());
+        }
+
+        // _PRx only describes the device, so it's evaluated without the lock
+        let new_resources = power_resources(device, state)?;
+        let old_resources = match old {
+            Some(old) => power_resources(device, old)?.unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let mut book = POWER.lock()?;
+        if book.devices.get(&key).copied() != old {
+            // another transition of the device won the race, start over from its state
+            continue;
+        }
+        return transition(&mut book, key, device, state, new_resources, old_resources);
+    }
+}
+
+/// Performs a transition with the resource lists snapshotted by set_device_power_state.
+/// The lock is held across _ON, _OFF and _PSx: a resource count only means the resource
+/// is on once its _ON returned, so another device sharing the resource must not run its
+/// _PSx in between. This costs no concurrency, as uACPI serializes AML execution anyway,
+/// and can't deadlock, as AML can't call back into here, a Notify is deferred to work.
+fn transition(
+    book: &mut Bookkeeping,
+    key: usize,
+    device: &NamespaceNode,
+    state: DevicePowerState,
+    new_resources: Option<Vec<NamespaceNode>>,
+    old_resources: Vec<NamespaceNode>,
+) -> Result<(), Status> {
+    let has_resources = new_resources.is_some();
+    let new_resources = new_resources.unwrap_or_default();
+    for (i, resource) in new_resources.iter().enumerate() {
+        if let Err(status) = book.reference(resource) {
+            for resource in &new_resources[..i] {
+                let _ = book.release(resource);
+            }
+            return Err(status);
+        }
+    }
+
+    let ps = match eval(device, state.ps_method(), []) {
+        Ok(_) => Ok(()),
+        Err(Status::NotFound) if has_resources || !matches!(
+            state,
+            DevicePowerState::D1 | DevicePowerState::D2
+        ) => Ok(()),
+        Err(status) => Err(status),
+    };
+    if let Err(status) = ps {
+        for resource in &new_resources {
+            let _ = book.release(resource);
+        }
+        return Err(status);
+    }
+
+    book.devices.insert(key, state);
+
+    let mut result = Ok(());
+    for resource in &old_resources {
+        if let Err(status) = book.release(resource) {
+            if result.is_ok() {
+                result = Err(status);
+            }
+        }
+    }
+    result
+}
+
+/// Returns the current power state of `device`.
+/// This is the value of _PSC if the device has one, otherwise the state last set
+/// with set_device_power_state or the shallowest state whose power resources are all on.
+/// Returns NotFound if the state cannot be determined.
+pub fn get_device_power_state(device: &NamespaceNode) -> Result<DevicePowerState, Status> {
+    match eval_simple_integer(device, c"_PSC") {
+        Ok(0) => return Ok(DevicePowerState::D0),
+        Ok(1) => return Ok(DevicePowerState::D1),
+        Ok(2) => return Ok(DevicePowerState::D2),
+        Ok(_) => return Ok(DevicePowerState::D3Hot),
+        Err(Status::NotFound) => {}
+        Err(status) => return Err(status),
+    }
+
+    if let Some(state) = POWER.lock()?.devices.get(&(device.raw() as usize)) {
+        return Ok(*state);
+    }
+
+    let mut has_resources = false;
+    for state in [DevicePowerState::D0, DevicePowerState::D1, DevicePowerState::D2, DevicePowerState::D3Hot] {
+        let resources = match power_resources(device, state)? {
+            Some(resources) if !resources.is_empty() => resources,
+            _ => continue,
+        };
+        has_resources = true;
+
+        let mut all_on = true;
+        for resource in &resources {
+            if eval_simple_integer(resource, c"_STA")? & u64::from(STA_PRESENT) == 0 {
+                all_on = false;
+                break;
+            }
+        }
+        if all_on {
+            return Ok(state);
+        }
+    }
+
+    if has_resources {
+        Ok(DevicePowerState::D3Cold)
+    } else {
+        Err(Status::NotFound)
+    }
+}
+
+/// How a device wakes the system, as described by its _PRW.
+#[derive(Debug)]
+pub struct PrwInfo {
+    /// The GPE block device of the wake GPE, or the root node for the FADT GPE blocks,
+    /// as taken by enable_gpe and the other GPE functions.
+    pub gpe_device: NamespaceNode,
+    /// The index of the wake GPE within its block.
+    pub gpe_index: u16,
+    /// The deepest sleep state the device can wake the system from, 0 to 5 for S0 to S5.
+    pub deepest_sleep_state: u8,
+    /// The power resources that must be on for the device to wake the system.
+    pub power_resources: Vec<NamespaceNode>,
+}
+
+/// Evaluates the _PRW of `device`, decoding both encodings of its GPE: a bare index
+/// into the FADT GPE blocks, or a package of a GPE block device and an index.
+/// Returns NotFound if the device has no _PRW, i.e. can't wake the system,
+/// and TypeMismatch if the package is malformed.
+pub fn eval_prw(device: &NamespaceNode) -> Result<PrwInfo, Status> {
+    let prw = eval(device, c"_PRW", [])?;
+    let gpe = prw.package_get(0).ok_or(Status::TypeMismatch)?;
+    let (gpe_device, gpe_index) = match gpe.get_int() {
+        Some(index) => (NamespaceNode::root(), index),
+        None => {
+            let block = gpe.package_get(0).ok_or(Status::TypeMismatch)?;
+            let index = gpe.package_get(1).and_then(|obj| obj.get_int()).ok_or(Status::TypeMismatch)?;
+            (block.resolve_as_node(device)?, index)
+        }
+    };
+    let deepest_sleep_state = prw.package_get(1)
+        .and_then(|obj| obj.get_int())
+        .ok_or(Status::TypeMismatch)?;
+
+    let power_resources = prw.get_package()
+        .ok_or(Status::TypeMismatch)?
+        .skip(2)
+        .map(|obj| obj.resolve_as_node(device))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(PrwInfo {
+        gpe_device,
+        gpe_index: u16::try_from(gpe_index).map_err(|_| Status::TypeMismatch)?,
+        deepest_sleep_state: u8::try_from(deepest_sleep_state).map_err(|_| Status::TypeMismatch)?,
+        power_resources,
+    })
+}
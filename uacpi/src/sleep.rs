@@ -1,4 +1,7 @@
-use crate::{PhysAddr, Status};
+use crate::kernel_api::get_kernel_api;
+#[cfg(not(feature = "reduced-hardware"))]
+use crate::require_full_hardware;
+use crate::{IOAddr, PhysAddr, Status};
 
 #[repr(C)]
 pub enum SleepState {
@@ -15,6 +18,8 @@ pub enum SleepState {
 /// `addr32` is the real mode entry-point address
 /// `addr64` is the protected mode entry-point address
 pub fn set_waking_vector(addr32: PhysAddr, addr64: PhysAddr) -> Result<(), Status> {
+    require_full_hardware()?;
+
     let status: Status = unsafe { uacpi_sys::uacpi_set_waking_vector(addr32.0, addr64.0).into() };
 
     match status {
@@ -84,3 +89,44 @@ pub fn reboot() -> Result<(), Status> {
         _ => Err(status)
     }
 }
+
+/// A way to reset the system, see reboot_with_fallback.
+pub enum RebootMethod<'a> {
+    /// The FADT reset register, see reboot.
+    FadtResetRegister,
+    /// Pulsing the reset line through the 8042 keyboard controller, only exists on x86 PCs.
+    KeyboardController,
+    /// A mechanism provided by the kernel, e.g. a triple fault.
+    Custom(&'a dyn Fn() -> Result<(), Status>),
+}
+
+impl RebootMethod<'_> {
+    fn attempt(&self) -> Result<(), Status> {
+        match self {
+            RebootMethod::FadtResetRegister => reboot(),
+            RebootMethod::KeyboardController => unsafe {
+                let api = get_kernel_api();
+                let handle = api.io_map(IOAddr::new(0x64), 1)?;
+                let ret = api.io_write(handle, 0, 1, 0xFE);
+                api.io_unmap(handle);
+                ret
+            },
+            RebootMethod::Custom(f) => f(),
+        }
+    }
+}
+
+/// Attempts to reset the system with each of `methods` in order.
+/// A method that doesn't fail is given 100ms to take effect before the next one is tried.
+/// Only returns if none of them worked, with the error of the last failing method,
+/// or HardwareTimeout if they all claimed success.
+pub fn reboot_with_fallback(methods: &[RebootMethod]) -> Status {
+    let mut last = Status::HardwareTimeout;
+    for method in methods {
+        match method.attempt() {
+            Ok(()) => get_kernel_api().sleep(100),
+            Err(status) => last = status,
+        }
+    }
+    last
+}
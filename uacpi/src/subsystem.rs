@@ -0,0 +1,157 @@
+use alloc::sync::Arc;
+use core::fmt::{self, Display};
+use crate::kernel_api::{set_kernel_api, KernelApi};
+use crate::{
+    finalize_gpe_initialization, init, init_auto, namespace_initialize, namespace_load,
+    set_interrupt_model, set_table_installation_handler, InterruptModel, LogLevel, PhysAddr,
+    SdtHdr, Status, TableInstallation,
+};
+
+/// The step of the bring-up done by AcpiSubsystemBuilder::build that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitError {
+    /// set_table_installation_handler failed, e.g. because a handler is already set.
+    TableOverride(Status),
+    /// init or init_auto failed, e.g. because the RSDP or a table is invalid.
+    Init(Status),
+    /// namespace_load failed, i.e. the DSDT or an SSDT could not be loaded.
+    NamespaceLoad(Status),
+    /// set_interrupt_model failed, e.g. because _PIC failed.
+    InterruptModel(Status),
+    /// namespace_initialize failed, i.e. an _INI or _REG failed.
+    NamespaceInitialize(Status),
+    /// finalize_gpe_initialization failed.
+    GpeInitialization(Status),
+}
+
+impl InitError {
+    /// Returns the status the failed step returned.
+    pub fn status(&self) -> Status {
+        match *self {
+            InitError::TableOverride(status)
+            | InitError::Init(status)
+            | InitError::NamespaceLoad(status)
+            | InitError::InterruptModel(status)
+            | InitError::NamespaceInitialize(status)
+            | InitError::GpeInitialization(status) => status,
+        }
+    }
+}
+
+impl Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let step = match self {
+            InitError::TableOverride(_) => "set_table_installation_handler",
+            InitError::Init(_) => "init",
+            InitError::NamespaceLoad(_) => "namespace_load",
+            InitError::InterruptModel(_) => "set_interrupt_model",
+            InitError::NamespaceInitialize(_) => "namespace_initialize",
+            InitError::GpeInitialization(_) => "finalize_gpe_initialization",
+        };
+        write!(f, "{} failed: {:?}", step, self.status())
+    }
+}
+
+/// Proof that uACPI was brought up completely by AcpiSubsystemBuilder::build.
+/// The free functions remain usable for callers that need a custom bring-up.
+#[derive(Debug)]
+pub struct AcpiSubsystem {
+    _private: (),
+}
+
+impl AcpiSubsystem {
+    pub fn builder() -> AcpiSubsystemBuilder {
+        AcpiSubsystemBuilder {
+            kernel_api: None,
+            rsdp: None,
+            log_level: LogLevel::INFO,
+            no_acpi_mode: false,
+            interrupt_model: None,
+            table_override: None,
+        }
+    }
+}
+
+/// Runs the uACPI bring-up in the required order, see AcpiSubsystem::builder.
+pub struct AcpiSubsystemBuilder {
+    kernel_api: Option<Arc<dyn KernelApi>>,
+    rsdp: Option<PhysAddr>,
+    log_level: LogLevel,
+    no_acpi_mode: bool,
+    interrupt_model: Option<InterruptModel>,
+    table_override: Option<fn(&SdtHdr) -> TableInstallation>,
+}
+
+impl AcpiSubsystemBuilder {
+    /// Sets the kernel api before anything else, it can also be set beforehand with set_kernel_api.
+    pub fn kernel_api(mut self, api: Arc<dyn KernelApi>) -> Self {
+        self.kernel_api = Some(api);
+        self
+    }
+
+    /// Sets the RSDP address, without one it's queried from KernelApi::get_rsdp.
+    pub fn rsdp(mut self, rsdp: PhysAddr) -> Self {
+        self.rsdp = Some(rsdp);
+        self
+    }
+
+    /// Sets the log level, INFO by default.
+    pub fn log_level(mut self, log_level: LogLevel) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    /// Leaves the hardware in legacy mode, see init.
+    pub fn no_acpi_mode(mut self, no_acpi_mode: bool) -> Self {
+        self.no_acpi_mode = no_acpi_mode;
+        self
+    }
+
+    /// Sets the interrupt model passed to _PIC, the firmware assumes Pic without one.
+    pub fn interrupt_model(mut self, model: InterruptModel) -> Self {
+        self.interrupt_model = Some(model);
+        self
+    }
+
+    /// Sets a handler that can deny or replace every table before it is installed,
+    /// see set_table_installation_handler. It's installed before init, so it also
+    /// sees the tables uACPI installs from the RSDT/XSDT, including the DSDT.
+    pub fn table_override(mut self, handler: fn(&SdtHdr) -> TableInstallation) -> Self {
+        self.table_override = Some(handler);
+        self
+    }
+
+    /// Runs init, namespace_load, set_interrupt_model, namespace_initialize
+    /// and finalize_gpe_initialization, stopping at the first step that fails.
+    /// uACPI installs the SCI handler itself during namespace_load.
+    /// GPEs are not an error on systems without them, i.e. hardware reduced ones.
+    pub fn build(self) -> Result<AcpiSubsystem, InitError> {
+        if let Some(api) = self.kernel_api {
+            set_kernel_api(api);
+        }
+
+        if let Some(handler) = self.table_override {
+            set_table_installation_handler(Some(handler)).map_err(InitError::TableOverride)?;
+        }
+
+        match self.rsdp {
+            Some(rsdp) => init(rsdp, self.log_level, self.no_acpi_mode),
+            None => init_auto(self.log_level, self.no_acpi_mode),
+        }.map_err(InitError::Init)?;
+
+        namespace_load().map_err(InitError::NamespaceLoad)?;
+
+        if let Some(model) = self.interrupt_model {
+            set_interrupt_model(model).map_err(InitError::InterruptModel)?;
+        }
+
+        namespace_initialize().map_err(InitError::NamespaceInitialize)?;
+
+        match finalize_gpe_initialization() {
+            Ok(()) | Err(Status::CompiledOut) => {}
+            Err(status) => return Err(InitError::GpeInitialization(status)),
+        }
+
+        Ok(AcpiSubsystem { _private: () })
+    }
+}
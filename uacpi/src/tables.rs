@@ -1,6 +1,12 @@
 use core::ffi::{c_void, CStr};
+use core::fmt::{self, Debug, Display};
 use core::mem::MaybeUninit;
-use crate::Status;
+use core::sync::atomic::AtomicU32;
+use crate::kernel_api::no_unwind;
+use crate::{AddressSpace, PhysAddr, Status};
+
+#[cfg(feature = "test-util")]
+pub mod test;
 
 pub const RSDP_SIGNATURE: &'static CStr = c"RSD PTR ";
 pub const RSDT_SIGNATURE: &'static CStr = c"RSDT";
@@ -16,6 +22,8 @@ pub const DSDT_SIGNATURE: &'static CStr = c"DSDT";
 pub const SSDT_SIGNATURE: &'static CStr = c"SSDT";
 pub const PSDT_SIGNATURE: &'static CStr = c"PSDT";
 pub const ECDT_SIGNATURE: &'static CStr = c"ECDT";
+pub const BGRT_SIGNATURE: &'static CStr = c"BGRT";
+pub const WAET_SIGNATURE: &'static CStr = c"WAET";
 pub type Gas = uacpi_sys::acpi_gas;
 pub type Rsdp = uacpi_sys::acpi_rsdp;
 pub type SdtHdr = uacpi_sys::acpi_sdt_hdr;
@@ -70,6 +78,214 @@ pub type Dsdt = uacpi_sys::acpi_dsdt;
 pub type Ssdt = uacpi_sys::acpi_ssdt;
 pub type Ecdt = uacpi_sys::acpi_ecdt;
 
+// uACPI has no definitions for these, so they are declared here following the spec.
+
+/// The Boot Graphics Resource Table, describing the image shown by the firmware during boot.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct Bgrt {
+    pub hdr: SdtHdr,
+    pub version: u16,
+    pub status: u8,
+    pub image_type: u8,
+    pub image_address: u64,
+    pub image_offset_x: u32,
+    pub image_offset_y: u32,
+}
+
+/// The Windows ACPI Emulated devices Table, describing which emulated devices
+/// of a virtual machine don't need the usual workarounds.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct Waet {
+    pub hdr: SdtHdr,
+    pub flags: u32,
+}
+
+// The accessors in this module read tables through the bindgen structs,
+// pin the layouts defined by the spec so a uACPI update that changes them fails to compile.
+const _: () = {
+    use core::mem::{offset_of, size_of};
+    assert!(size_of::<SdtHdr>() == 36);
+    assert!(offset_of!(SdtHdr, length) == 4);
+    assert!(offset_of!(SdtHdr, oem_id) == 10);
+    assert!(offset_of!(SdtHdr, oem_table_id) == 16);
+    assert!(offset_of!(SdtHdr, oem_revision) == 24);
+    assert!(offset_of!(Fadt, iapc_boot_arch) == 109);
+    assert!(offset_of!(Fadt, flags) == 112);
+    assert!(size_of::<Madt>() == 44);
+    assert!(size_of::<MadtIoapic>() == 12);
+    assert!(size_of::<MadtIrqSourceOverride>() == 10);
+    assert!(offset_of!(MadtIrqSourceOverride, gsi) == 4);
+    assert!(offset_of!(MadtLapic, flags) == 4);
+    assert!(offset_of!(MadtX2apic, flags) == 8);
+    assert!(size_of::<Mcfg>() == 44);
+    assert!(size_of::<McfgAllocation>() == 16);
+    assert!(size_of::<Slit>() == 44);
+    assert!(offset_of!(Slit, num_localities) == 36);
+    assert!(offset_of!(Gtdt, platform_timer_count) == 88);
+    assert!(offset_of!(Gtdt, platform_timer_offset) == 92);
+    assert!(size_of::<GtdtEntryHdr>() == 3);
+    assert!(size_of::<GtdtTimer>() == 20);
+    assert!(offset_of!(GtdtTimer, timer_count) == 12);
+    assert!(offset_of!(GtdtTimer, timer_offset) == 16);
+    assert!(size_of::<GtdtTimerEntry>() == 40);
+    assert!(size_of::<GtdtWatchdog>() == 28);
+    assert!(size_of::<Rsdt>() == 36);
+    assert!(size_of::<Xsdt>() == 36);
+    assert!(size_of::<Facs>() == 64);
+    assert!(offset_of!(Facs, hardware_signature) == 8);
+    assert!(offset_of!(Facs, global_lock) == 16);
+    assert!(offset_of!(Facs, x_firmware_waking_vector) == 24);
+    assert!(size_of::<Bgrt>() == 56);
+    assert!(offset_of!(Bgrt, image_address) == 40);
+    assert!(size_of::<Waet>() == 40);
+};
+
+/// A view of the common header of a mapped table.
+#[repr(transparent)]
+pub struct TableHeader(SdtHdr);
+
+impl TableHeader {
+    /// Views the header of a table as a TableHeader.
+    ///
+    /// # Safety
+    /// `hdr` must be followed by the rest of the table, i.e. `length` bytes must be mapped.
+    pub unsafe fn from_hdr(hdr: &SdtHdr) -> &Self {
+        &*(hdr as *const SdtHdr as *const Self)
+    }
+
+    pub fn signature(&self) -> [u8; 4] {
+        self.0.signature.map(|c| c as u8)
+    }
+
+    /// Returns the OEM id with the padding removed, empty if it is not valid ASCII.
+    pub fn oem_id(&self) -> &str {
+        header_str(&self.0.oem_id)
+    }
+
+    /// Returns the OEM table id with the padding removed, empty if it is not valid ASCII.
+    pub fn oem_table_id(&self) -> &str {
+        header_str(&self.0.oem_table_id)
+    }
+
+    pub fn oem_revision(&self) -> u32 {
+        u32::from_le(self.0.oem_revision)
+    }
+
+    pub fn revision(&self) -> u8 {
+        self.0.revision
+    }
+
+    /// Returns the length of the whole table including the header.
+    pub fn length(&self) -> u32 {
+        u32::from_le(self.0.length)
+    }
+
+    /// Checks whether all bytes of the table sum up to zero.
+    pub fn checksum_valid(&self) -> bool {
+        let bytes = unsafe {
+            core::slice::from_raw_parts(self as *const Self as *const u8, self.length() as usize)
+        };
+        bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) == 0
+    }
+}
+
+impl Debug for TableHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TableHeader")
+            .field("signature", &core::str::from_utf8(&self.signature()).unwrap_or("????"))
+            .field("length", &self.length())
+            .field("revision", &self.revision())
+            .field("oem_id", &self.oem_id())
+            .field("oem_table_id", &self.oem_table_id())
+            .field("oem_revision", &self.oem_revision())
+            .finish()
+    }
+}
+
+/// Converts a space or null padded header field to a string.
+fn header_str(field: &[core::ffi::c_char]) -> &str {
+    let bytes = unsafe { core::slice::from_raw_parts(field.as_ptr() as *const u8, field.len()) };
+    let len = bytes.iter().rposition(|b| *b != b' ' && *b != 0).map_or(0, |i| i + 1);
+    match core::str::from_utf8(&bytes[..len]) {
+        Ok(str) if str.is_ascii() => str,
+        _ => "",
+    }
+}
+
+/// A decoded view of a GAS (Generic Address Structure).
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct GenericAddress(pub Gas);
+
+impl GenericAddress {
+    /// Views a GAS embedded in a table, e.g. a FADT register, as a GenericAddress.
+    pub fn from_gas(gas: &Gas) -> &Self {
+        unsafe { &*(gas as *const Gas as *const Self) }
+    }
+
+    /// Returns the address space, None for reserved or OEM defined spaces.
+    pub fn address_space(&self) -> Option<AddressSpace> {
+        AddressSpace::from_raw(self.0.address_space_id as _)
+    }
+
+    pub fn bit_width(&self) -> u8 {
+        self.0.register_bit_width
+    }
+
+    pub fn bit_offset(&self) -> u8 {
+        self.0.register_bit_offset
+    }
+
+    /// Returns the access size in bytes, None if it is undefined.
+    pub fn access_size(&self) -> Option<u8> {
+        match self.0.access_size {
+            size @ 1..=4 => Some(1 << (size - 1)),
+            _ => None,
+        }
+    }
+
+    pub fn address(&self) -> u64 {
+        u64::from_le(self.0.address)
+    }
+
+    /// Checks whether the GAS is all zeroes, i.e. the register doesn't exist.
+    pub fn is_null(&self) -> bool {
+        self.address() == 0 && self.bit_width() == 0
+    }
+}
+
+impl From<Gas> for GenericAddress {
+    fn from(gas: Gas) -> Self {
+        Self(gas)
+    }
+}
+
+impl Display for GenericAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address_space() {
+            Some(space) => write!(f, "{:?}", space)?,
+            None => write!(f, "Space({:#x})", self.0.address_space_id)?,
+        }
+        write!(f, "@{:#X} width={}", self.address(), self.bit_width())?;
+        if self.bit_offset() != 0 {
+            write!(f, " offset={}", self.bit_offset())?;
+        }
+        if let Some(size) = self.access_size() {
+            write!(f, " access={}", size)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for GenericAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GenericAddress({})", self)
+    }
+}
+
+/// A reference to an installed table, released when dropped.
 #[repr(transparent)]
 pub struct Table(pub(crate) uacpi_sys::uacpi_table);
 
@@ -83,6 +299,45 @@ impl Table {
     pub fn get_index(&self) -> usize {
         self.0.index
     }
+
+    /// Returns the header of the table.
+    pub fn header(&self) -> &TableHeader {
+        unsafe { TableHeader::from_hdr(&*self.0.__bindgen_anon_1.hdr) }
+    }
+
+    /// Returns the whole table including the header, as many bytes as the header length says.
+    /// The slice borrows the table and is only valid while the table is referenced.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            let hdr = self.0.__bindgen_anon_1.hdr;
+            let length = u32::from_le(core::ptr::addr_of!((*hdr).length).read_unaligned());
+            core::slice::from_raw_parts(hdr as *const u8, length as usize)
+        }
+    }
+}
+
+impl Drop for Table {
+    fn drop(&mut self) {
+        unsafe { uacpi_sys::uacpi_table_unref(&mut self.0) };
+    }
+}
+
+/// Sets up access to the tables before init, e.g. to read the MADT before a heap exists.
+/// The RSDP is queried from KernelApi::get_rsdp, so the kernel api must be set first.
+/// `buffer` is used to store the table list, every installed table needs an entry of
+/// a few dozen bytes, a single 4KiB page is enough for virtually all machines
+/// and OutOfMemory is returned otherwise.
+/// uACPI keeps using the buffer until init copies the table list to the heap,
+/// after that it may be reused.
+pub fn setup_early_table_access(buffer: &'static mut [u8]) -> Result<(), Status> {
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_setup_early_table_access(buffer.as_mut_ptr().cast(), buffer.len()).into()
+    };
+
+    match status {
+        Status::Ok => Ok(()),
+        _ => Err(status)
+    }
 }
 
 /// Finds a table with a given signature.
@@ -98,6 +353,35 @@ pub fn table_find_by_signature(signature: &CStr) -> Result<Table, Status> {
     }
 }
 
+/// Finds the next table with the same signature as `table`, e.g. the next SSDT.
+/// Returns NotFound after the last one.
+pub fn table_find_next(table: &Table) -> Result<Table, Status> {
+    let mut ret = table.0;
+    // uACPI releases the table passed in, which still belongs to `table`
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_table_ref(&mut ret);
+        uacpi_sys::uacpi_table_find_next_with_same_signature(&mut ret).into()
+    };
+
+    match status {
+        Status::Ok => Ok(Table(ret)),
+        _ => Err(status)
+    }
+}
+
+/// Iterates over all installed tables with a given signature, in installation order.
+/// uACPI has no API to enumerate tables regardless of their signature,
+/// so every signature of interest has to be listed, e.g. from the XSDT.
+/// Iteration stops at the first error, not only at NotFound.
+pub fn tables_with_signature(signature: &CStr) -> impl Iterator<Item = Table> {
+    let mut next = table_find_by_signature(signature).ok();
+    core::iter::from_fn(move || {
+        let table = next.take()?;
+        next = table_find_next(&table).ok();
+        Some(table)
+    })
+}
+
 /// Returns the pointer to a sanitized internal version of FADT.
 /// The revision is guaranteed to be correct. All of the registers are converted
 /// to GAS format. Fields that might contain garbage are cleared.
@@ -112,3 +396,455 @@ pub fn table_fadt() -> Result<&'static Fadt, Status> {
         _ => Err(status)
     }
 }
+
+// IA-PC boot architecture flags of the FADT
+const IAPC_LEGACY_DEVICES: u16 = 1 << 0;
+const IAPC_8042: u16 = 1 << 1;
+const IAPC_VGA_NOT_PRESENT: u16 = 1 << 2;
+const IAPC_MSI_NOT_SUPPORTED: u16 = 1 << 3;
+const IAPC_CMOS_RTC_NOT_PRESENT: u16 = 1 << 5;
+
+fn fadt_iapc_boot_arch(fadt: &Fadt) -> u16 {
+    u16::from_le(fadt.iapc_boot_arch)
+}
+
+/// Checks whether the motherboard has legacy devices, such as serial ports,
+/// that are not enumerated through ACPI or PnP.
+pub fn fadt_legacy_devices(fadt: &Fadt) -> bool {
+    fadt_iapc_boot_arch(fadt) & IAPC_LEGACY_DEVICES != 0
+}
+
+/// Checks whether an 8042 keyboard controller is present.
+/// Firmware before FADT revision 2 doesn't report this, so false doesn't rule one out there.
+pub fn fadt_has_8042(fadt: &Fadt) -> bool {
+    fadt_iapc_boot_arch(fadt) & IAPC_8042 != 0
+}
+
+/// Checks whether VGA hardware may be probed, i.e. the FADT doesn't report it as absent.
+pub fn fadt_has_vga(fadt: &Fadt) -> bool {
+    fadt_iapc_boot_arch(fadt) & IAPC_VGA_NOT_PRESENT == 0
+}
+
+/// Checks whether MSI must not be enabled on this platform.
+pub fn fadt_no_msi(fadt: &Fadt) -> bool {
+    fadt_iapc_boot_arch(fadt) & IAPC_MSI_NOT_SUPPORTED != 0
+}
+
+/// Checks whether the CMOS RTC may be accessed, i.e. the FADT doesn't report it as absent.
+/// Without it the time has to be read through the UEFI runtime services or similar.
+pub fn fadt_has_cmos_rtc(fadt: &Fadt) -> bool {
+    fadt_iapc_boot_arch(fadt) & IAPC_CMOS_RTC_NOT_PRESENT == 0
+}
+
+/// FACS flag: the firmware supports S4 through the S4BIOS_REQ value of the SMI command port.
+pub const FACS_S4BIOS: u32 = 1 << 0;
+/// FACS flag: the firmware can jump to x_firmware_waking_vector in 64-bit mode.
+pub const FACS_64BIT_WAKE_SUPPORTED: u32 = 1 << 1;
+
+/// Returns the hardware signature, which the firmware changes if the hardware configuration
+/// changed while the system was sleeping. A kernel resuming from S4 compares it with the value
+/// saved before going to sleep, and has to boot normally if it differs.
+pub fn facs_hardware_signature(facs: &Facs) -> u32 {
+    u32::from_le(facs.hardware_signature)
+}
+
+/// Returns the 32-bit real mode waking vector, usually set via set_waking_vector.
+pub fn facs_firmware_waking_vector(facs: &Facs) -> u32 {
+    u32::from_le(facs.firmware_waking_vector)
+}
+
+/// Returns the extended waking vector, which takes precedence over the 32-bit one if not 0.
+pub fn facs_x_firmware_waking_vector(facs: &Facs) -> u64 {
+    u64::from_le(facs.x_firmware_waking_vector)
+}
+
+/// Returns the flags, see FACS_S4BIOS and FACS_64BIT_WAKE_SUPPORTED.
+pub fn facs_flags(facs: &Facs) -> u32 {
+    u32::from_le(facs.flags)
+}
+
+/// Returns the global lock dword shared with the firmware, None if it's misaligned,
+/// which only happens if the FACS doesn't have the 64 byte alignment the spec requires.
+/// uACPI implements the acquire/release protocol, including the pending bit and
+/// the GBL_RLS handshake, so this is only meant for inspection, e.g. in a debugger.
+/// acquire_global_lock must be used to take the lock.
+pub fn facs_global_lock(facs: &Facs) -> Option<&AtomicU32> {
+    let lock = core::ptr::addr_of!(facs.global_lock) as *mut u32;
+    if !lock.is_aligned() {
+        return None;
+    }
+
+    // the firmware modifies the lock concurrently, so it's only ever accessed atomically
+    Some(unsafe { AtomicU32::from_ptr(lock) })
+}
+
+/// The flags of a MADT Local APIC or x2APIC entry, which decide whether the OS may start the processor.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LapicFlags(pub u32);
+
+impl LapicFlags {
+    /// The processor is ready for use.
+    pub fn is_enabled(&self) -> bool {
+        self.0 & uacpi_sys::ACPI_PIC_ENABLED != 0
+    }
+
+    /// The processor is disabled but may be enabled at runtime, e.g. hotplugged.
+    /// Only meaningful if the processor is not enabled.
+    pub fn is_online_capable(&self) -> bool {
+        self.0 & uacpi_sys::ACPI_PIC_ONLINE_CAPABLE != 0
+    }
+
+    /// The OS may bring the processor up, now or after it was hotplugged.
+    /// Entries for which this is false must be ignored.
+    pub fn is_usable(&self) -> bool {
+        self.is_enabled() || self.is_online_capable()
+    }
+}
+
+pub fn madt_lapic_flags(lapic: &MadtLapic) -> LapicFlags {
+    LapicFlags(u32::from_le(lapic.flags))
+}
+
+pub fn madt_x2apic_flags(x2apic: &MadtX2apic) -> LapicFlags {
+    LapicFlags(u32::from_le(x2apic.flags))
+}
+
+/// What to do with a table that is about to be installed.
+#[derive(Debug, Clone, Copy)]
+pub enum TableInstallation {
+    /// Install the table as is.
+    Allow,
+    /// Don't install the table.
+    Deny,
+    /// Install this table instead, e.g. a patched DSDT.
+    /// The reference must point at the whole table, i.e. `length` bytes starting at the
+    /// header. uACPI doesn't copy it and keeps using it for as long as the table is
+    /// installed, hence the `'static` lifetime, e.g. a leaked Box or an `include_bytes!`.
+    VirtualOverride(&'static SdtHdr),
+    /// Install the table at this physical address instead.
+    PhysicalOverride(PhysAddr),
+}
+
+static mut TABLE_INSTALLATION_HANDLER: Option<fn(&SdtHdr) -> TableInstallation> = None;
+
+unsafe extern "C" fn table_installation_cb(
+    hdr: *mut SdtHdr,
+    out_override_address: *mut u64
+) -> uacpi_sys::uacpi_table_installation_disposition {
+    no_unwind(|| {
+        let Some(handler) = TABLE_INSTALLATION_HANDLER else {
+            return uacpi_sys::UACPI_TABLE_INSTALLATION_DISPOSITON_ALLOW;
+        };
+
+        match handler(&*hdr) {
+            TableInstallation::Allow => uacpi_sys::UACPI_TABLE_INSTALLATION_DISPOSITON_ALLOW,
+            TableInstallation::Deny => uacpi_sys::UACPI_TABLE_INSTALLATION_DISPOSITON_DENY,
+            TableInstallation::VirtualOverride(table) => {
+                *out_override_address = table as *const SdtHdr as u64;
+                uacpi_sys::UACPI_TABLE_INSTALLATION_DISPOSITON_VIRTUAL_OVERRIDE
+            }
+            TableInstallation::PhysicalOverride(addr) => {
+                *out_override_address = addr.as_u64();
+                uacpi_sys::UACPI_TABLE_INSTALLATION_DISPOSITON_PHYSICAL_OVERRIDE
+            }
+        }
+    })
+}
+
+/// Sets a handler that is called with the header of every table before it is installed,
+/// including the DSDT and the tables loaded by AML, allowing the OS to deny or
+/// substitute it. Set this before init to be able to override the DSDT.
+/// Passing None removes the handler.
+pub fn set_table_installation_handler(
+    handler: Option<fn(&SdtHdr) -> TableInstallation>
+) -> Result<(), Status> {
+    let status: Status = unsafe {
+        uacpi_sys::uacpi_set_table_installation_handler(
+            handler.map(|_| table_installation_cb as _)
+        ).into()
+    };
+
+    match status {
+        Status::Ok => {
+            // only replace the handler once uACPI accepted it, a rejected one would
+            // otherwise take over the callback that is still installed
+            unsafe { TABLE_INSTALLATION_HANDLER = handler };
+            Ok(())
+        }
+        _ => Err(status)
+    }
+}
+
+// The parsers below work on byte slices, so they can be fed arbitrary data, see the fuzz module.
+// The variants taking a Table read it through Table::as_bytes.
+
+/// Reinterprets the start of `bytes` as a T, None if it's too short.
+fn view<T>(bytes: &[u8]) -> Option<&T> {
+    // only the packed table structs are viewed, which can start at any byte
+    const { assert!(core::mem::align_of::<T>() == 1) };
+    if bytes.len() < core::mem::size_of::<T>() {
+        return None;
+    }
+    Some(unsafe { &*(bytes.as_ptr() as *const T) })
+}
+
+/// Returns the table at the start of `bytes` along with its bytes, cut off at the length
+/// in its header. None if `bytes` is too short to hold a T.
+fn table_in<T>(bytes: &[u8]) -> Option<(&T, &[u8])> {
+    let table = view::<T>(bytes)?;
+    let length = u32::from_le(view::<SdtHdr>(bytes)?.length) as usize;
+    Some((table, &bytes[..length.min(bytes.len())]))
+}
+
+/// Returns the allocations (ECAM regions) described by `mcfg`, an MCFG found through uACPI.
+/// Iteration stops at the table length, so a truncated trailing entry is skipped.
+pub fn mcfg_allocations(mcfg: &Table) -> impl Iterator<Item = &McfgAllocation> {
+    mcfg_allocations_from_bytes(mcfg.as_bytes())
+}
+
+/// Same as mcfg_allocations, except the MCFG is read from `bytes`,
+/// which may be shorter than the table length.
+pub fn mcfg_allocations_from_bytes(bytes: &[u8]) -> impl Iterator<Item = &McfgAllocation> {
+    let entries = table_in::<Mcfg>(bytes)
+        .and_then(|(_, table)| table.get(core::mem::size_of::<Mcfg>()..))
+        .unwrap_or(&[]);
+
+    entries.chunks_exact(core::mem::size_of::<McfgAllocation>()).filter_map(view)
+}
+
+/// Returns the number of localities described by the SLIT.
+pub fn slit_locality_count(slit: &Slit) -> u64 {
+    u64::from_le(slit.num_localities)
+}
+
+/// Returns the relative distance between the localities `from` and `to` of `slit`,
+/// a SLIT found through uACPI, where 10 is the distance of a locality to itself.
+/// Returns None if either is out of range, or if the matrix doesn't fit into the table length.
+pub fn slit_distance(slit: &Table, from: u64, to: u64) -> Option<u8> {
+    slit_distance_from_bytes(slit.as_bytes(), from, to)
+}
+
+/// Same as slit_distance, except the SLIT is read from `bytes`,
+/// which may be shorter than the table length.
+pub fn slit_distance_from_bytes(bytes: &[u8], from: u64, to: u64) -> Option<u8> {
+    let (slit, table) = table_in::<Slit>(bytes)?;
+    let count = slit_locality_count(slit);
+    if from >= count || to >= count {
+        return None;
+    }
+
+    let index = usize::try_from(from.checked_mul(count)?.checked_add(to)?).ok()?;
+    table.get(core::mem::size_of::<Slit>().checked_add(index)?).copied()
+}
+
+/// An interrupt controller structure of the MADT.
+#[derive(Debug, Clone, Copy)]
+pub enum MadtEntry<'a> {
+    Lapic(&'a MadtLapic),
+    Ioapic(&'a MadtIoapic),
+    /// Maps an ISA IRQ to a different GSI or polarity/trigger mode, e.g. the SCI.
+    IrqSourceOverride(&'a MadtIrqSourceOverride),
+    X2apic(&'a MadtX2apic),
+    /// A structure of a type this crate doesn't decode, or one too short for its type.
+    Unknown(&'a EntryHdr),
+}
+
+/// Returns the interrupt controller structures described by `madt`, a MADT found through uACPI.
+/// Iteration stops at the first entry that doesn't fit into the table length.
+pub fn madt_entries(madt: &Table) -> impl Iterator<Item = MadtEntry<'_>> {
+    madt_entries_from_bytes(madt.as_bytes())
+}
+
+/// Same as madt_entries, except the MADT is read from `bytes`,
+/// which may be shorter than the table length.
+pub fn madt_entries_from_bytes(bytes: &[u8]) -> impl Iterator<Item = MadtEntry<'_>> {
+    let mut rest = table_in::<Madt>(bytes)
+        .and_then(|(_, table)| table.get(core::mem::size_of::<Madt>()..))
+        .unwrap_or(&[]);
+
+    core::iter::from_fn(move || {
+        let hdr = view::<EntryHdr>(rest)?;
+        let length = hdr.length as usize;
+        if length < core::mem::size_of::<EntryHdr>() {
+            return None;
+        }
+        let entry = rest.get(..length)?;
+        rest = &rest[length..];
+
+        Some(match hdr.type_ as u32 {
+            uacpi_sys::ACPI_MADT_ENTRY_TYPE_LAPIC => {
+                view(entry).map_or(MadtEntry::Unknown(hdr), MadtEntry::Lapic)
+            }
+            uacpi_sys::ACPI_MADT_ENTRY_TYPE_IOAPIC => {
+                view(entry).map_or(MadtEntry::Unknown(hdr), MadtEntry::Ioapic)
+            }
+            uacpi_sys::ACPI_MADT_ENTRY_TYPE_INTERRUPT_SOURCE_OVERRIDE => {
+                view(entry).map_or(MadtEntry::Unknown(hdr), MadtEntry::IrqSourceOverride)
+            }
+            uacpi_sys::ACPI_MADT_ENTRY_TYPE_LOCAL_X2APIC => {
+                view(entry).map_or(MadtEntry::Unknown(hdr), MadtEntry::X2apic)
+            }
+            _ => MadtEntry::Unknown(hdr),
+        })
+    })
+}
+
+/// A platform timer structure of the GTDT.
+#[derive(Debug, Clone, Copy)]
+pub enum GtdtEntry<'a> {
+    /// A GT block along with its timer frames.
+    Timer(GtdtTimerBlock<'a>),
+    /// An SBSA generic watchdog.
+    Watchdog(&'a GtdtWatchdog),
+    /// A structure of a type this crate doesn't know about, or one too short for its type.
+    Unknown(&'a GtdtEntryHdr),
+}
+
+/// A GT block of the GTDT, holding the bytes of the whole structure,
+/// which bound its timer frames.
+#[derive(Debug, Clone, Copy)]
+pub struct GtdtTimerBlock<'a> {
+    timer: &'a GtdtTimer,
+    bytes: &'a [u8],
+}
+
+impl<'a> GtdtTimerBlock<'a> {
+    pub fn timer(&self) -> &'a GtdtTimer {
+        self.timer
+    }
+
+    /// Returns the timer frames of the block.
+    /// Iteration stops at the length of the block, so a truncated trailing frame is skipped.
+    pub fn frames(&self) -> impl Iterator<Item = &'a GtdtTimerEntry> {
+        let frames = self.bytes.get(u32::from_le(self.timer.timer_offset) as usize..).unwrap_or(&[]);
+
+        frames.chunks_exact(core::mem::size_of::<GtdtTimerEntry>())
+            .take(u32::from_le(self.timer.timer_count) as usize)
+            .filter_map(view)
+    }
+}
+
+/// Returns the platform timer structures described by `gtdt`, a GTDT found through uACPI.
+/// Iteration stops at the first entry that doesn't fit into the table length.
+pub fn gtdt_entries(gtdt: &Table) -> impl Iterator<Item = GtdtEntry<'_>> {
+    gtdt_entries_from_bytes(gtdt.as_bytes())
+}
+
+/// Same as gtdt_entries, except the GTDT is read from `bytes`,
+/// which may be shorter than the table length.
+pub fn gtdt_entries_from_bytes(bytes: &[u8]) -> impl Iterator<Item = GtdtEntry<'_>> {
+    let (mut offset, mut remaining, table) = match table_in::<Gtdt>(bytes) {
+        Some((gtdt, table)) => (
+            u32::from_le(gtdt.platform_timer_offset) as usize,
+            u32::from_le(gtdt.platform_timer_count),
+            table,
+        ),
+        None => (0, 0, &[][..]),
+    };
+
+    core::iter::from_fn(move || {
+        if remaining == 0 {
+            return None;
+        }
+        let rest = table.get(offset..)?;
+        let hdr = view::<GtdtEntryHdr>(rest)?;
+        let length = u16::from_le(hdr.length) as usize;
+        if length < core::mem::size_of::<GtdtEntryHdr>() {
+            return None;
+        }
+        let entry = rest.get(..length)?;
+        offset += length;
+        remaining -= 1;
+
+        Some(match hdr.type_ as u32 {
+            uacpi_sys::ACPI_GTDT_ENTRY_TYPE_TIMER => match view(entry) {
+                Some(timer) => GtdtEntry::Timer(GtdtTimerBlock { timer, bytes: entry }),
+                None => GtdtEntry::Unknown(hdr),
+            },
+            uacpi_sys::ACPI_GTDT_ENTRY_TYPE_WATCHDOG => {
+                view(entry).map_or(GtdtEntry::Unknown(hdr), GtdtEntry::Watchdog)
+            }
+            _ => GtdtEntry::Unknown(hdr),
+        })
+    })
+}
+
+/// Returns the entries following the header of an RSDT or XSDT, each `N` bytes wide.
+fn sdt_entries<const N: usize>(bytes: &[u8]) -> impl Iterator<Item = [u8; N]> + '_ {
+    let entries = table_in::<SdtHdr>(bytes)
+        .and_then(|(_, table)| table.get(core::mem::size_of::<SdtHdr>()..))
+        .unwrap_or(&[]);
+
+    // read bytewise, since the XSDT entries are only 4 byte aligned
+    entries.chunks_exact(N).map(|entry| entry.try_into().unwrap())
+}
+
+/// Returns the physical addresses of the tables listed in `rsdt`, an RSDT found through uACPI,
+/// which holds 32-bit ones.
+/// Iteration stops at the table length, so a truncated trailing entry is skipped.
+pub fn rsdt_entries(rsdt: &Table) -> impl Iterator<Item = PhysAddr> + '_ {
+    rsdt_entries_from_bytes(rsdt.as_bytes())
+}
+
+/// Same as rsdt_entries, except the RSDT is read from `bytes`,
+/// which may be shorter than the table length.
+pub fn rsdt_entries_from_bytes(bytes: &[u8]) -> impl Iterator<Item = PhysAddr> + '_ {
+    sdt_entries::<4>(bytes)
+        .filter_map(|entry| PhysAddr::try_new(u32::from_le_bytes(entry) as u64).ok())
+}
+
+/// Returns the physical addresses of the tables listed in `xsdt`, an XSDT found through uACPI,
+/// which holds 64-bit ones.
+/// Iteration stops at the table length, so a truncated trailing entry is skipped.
+/// Addresses that don't fit into the physical address width uACPI was built with are skipped.
+pub fn xsdt_entries(xsdt: &Table) -> impl Iterator<Item = PhysAddr> + '_ {
+    xsdt_entries_from_bytes(xsdt.as_bytes())
+}
+
+/// Same as xsdt_entries, except the XSDT is read from `bytes`,
+/// which may be shorter than the table length.
+pub fn xsdt_entries_from_bytes(bytes: &[u8]) -> impl Iterator<Item = PhysAddr> + '_ {
+    sdt_entries::<8>(bytes)
+        .filter_map(|entry| PhysAddr::try_new(u64::from_le_bytes(entry)).ok())
+}
+
+/// BGRT image type: the image is a bitmap (BMP).
+pub const BGRT_IMAGE_TYPE_BITMAP: u8 = 0;
+
+const BGRT_STATUS_DISPLAYED: u8 = 1 << 0;
+const WAET_RTC_GOOD: u32 = 1 << 0;
+const WAET_PM_TIMER_GOOD: u32 = 1 << 1;
+
+/// Returns the physical address of the boot image, its format is given by bgrt_image_type.
+/// Returns InvalidArgument if the address doesn't fit into the physical address width
+/// uACPI was built with, which only firmware with a bogus BGRT reports.
+pub fn bgrt_image_address(bgrt: &Bgrt) -> Result<PhysAddr, Status> {
+    PhysAddr::try_new(u64::from_le(bgrt.image_address))
+}
+
+/// Returns the format of the boot image, see BGRT_IMAGE_TYPE_BITMAP.
+pub fn bgrt_image_type(bgrt: &Bgrt) -> u8 {
+    bgrt.image_type
+}
+
+/// Returns the position of the upper left corner of the boot image on the screen, in pixels.
+pub fn bgrt_image_offset(bgrt: &Bgrt) -> (u32, u32) {
+    (u32::from_le(bgrt.image_offset_x), u32::from_le(bgrt.image_offset_y))
+}
+
+/// Returns whether the boot image is currently displayed on the screen.
+pub fn bgrt_is_displayed(bgrt: &Bgrt) -> bool {
+    bgrt.status & BGRT_STATUS_DISPLAYED != 0
+}
+
+/// Returns whether the emulated RTC doesn't need register C to be read after every access.
+pub fn waet_rtc_good(waet: &Waet) -> bool {
+    u32::from_le(waet.flags) & WAET_RTC_GOOD != 0
+}
+
+/// Returns whether the emulated PM timer can be read once,
+/// instead of repeatedly until two reads are consistent.
+pub fn waet_pm_timer_good(waet: &Waet) -> bool {
+    u32::from_le(waet.flags) & WAET_PM_TIMER_GOOD != 0
+}
@@ -0,0 +1,287 @@
+use alloc::vec::Vec;
+use core::mem::size_of;
+use crate::{Fadt, PhysAddr};
+
+const OEM_ID: &[u8; 6] = b"UACPI ";
+const OEM_TABLE_ID: &[u8; 8] = b"SYNTHETC";
+const SDT_HEADER: usize = 36;
+const FACS_LENGTH: usize = 64;
+
+/// Builds an in-memory table set for tests: an RSDP pointing to an XSDT that lists a FADT,
+/// a MADT and any extra tables, with the FADT pointing to a FACS and a DSDT.
+/// Lengths, checksums and the addresses between the tables are filled in by write_to,
+/// e.g. into the physical memory of a MockKernelApi.
+pub struct SyntheticTables {
+    fadt: Fadt,
+    dsdt: Vec<u8>,
+    lapic_address: u32,
+    madt_flags: u32,
+    madt_entries: Vec<u8>,
+    tables: Vec<([u8; 4], u8, Vec<u8>)>,
+}
+
+impl Default for SyntheticTables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyntheticTables {
+    /// Creates a table set with a zeroed FADT, an empty DSDT and a MADT without entries.
+    pub fn new() -> Self {
+        Self {
+            fadt: unsafe { core::mem::zeroed() },
+            dsdt: Vec::new(),
+            lapic_address: 0xFEE0_0000,
+            madt_flags: 1, // PCAT_COMPAT
+            madt_entries: Vec::new(),
+            tables: Vec::new(),
+        }
+    }
+
+    /// Modifies the FADT, e.g. to set flags or the SCI interrupt.
+    /// The header and the FACS and DSDT pointers are overwritten by write_to.
+    pub fn fadt(mut self, f: impl FnOnce(&mut Fadt)) -> Self {
+        f(&mut self.fadt);
+        self
+    }
+
+    /// Sets the AML of the DSDT, i.e. the table without its header.
+    pub fn dsdt(mut self, aml: &[u8]) -> Self {
+        self.dsdt = aml.to_vec();
+        self
+    }
+
+    /// Sets the local APIC address and flags of the MADT.
+    pub fn madt(mut self, lapic_address: u32, flags: u32) -> Self {
+        self.lapic_address = lapic_address;
+        self.madt_flags = flags;
+        self
+    }
+
+    /// Appends a Local APIC structure to the MADT.
+    pub fn lapic(self, uid: u8, apic_id: u8, flags: u32) -> Self {
+        let mut entry = [0; 8];
+        entry[..4].copy_from_slice(&[0, 8, uid, apic_id]);
+        entry[4..].copy_from_slice(&flags.to_le_bytes());
+        self.madt_entry(&entry)
+    }
+
+    /// Appends an I/O APIC structure to the MADT.
+    pub fn ioapic(self, id: u8, address: u32, gsi_base: u32) -> Self {
+        let mut entry = [0; 12];
+        entry[..4].copy_from_slice(&[1, 12, id, 0]);
+        entry[4..8].copy_from_slice(&address.to_le_bytes());
+        entry[8..].copy_from_slice(&gsi_base.to_le_bytes());
+        self.madt_entry(&entry)
+    }
+
+    /// Appends an interrupt source override for the ISA IRQ `source` to the MADT.
+    pub fn irq_source_override(self, source: u8, gsi: u32, flags: u16) -> Self {
+        let mut entry = [0; 10];
+        entry[..4].copy_from_slice(&[2, 10, 0, source]);
+        entry[4..8].copy_from_slice(&gsi.to_le_bytes());
+        entry[8..].copy_from_slice(&flags.to_le_bytes());
+        self.madt_entry(&entry)
+    }
+
+    /// Appends a raw structure to the MADT, including its type and length.
+    pub fn madt_entry(mut self, entry: &[u8]) -> Self {
+        self.madt_entries.extend_from_slice(entry);
+        self
+    }
+
+    /// Adds a table listed in the XSDT, `body` is the table without its header.
+    pub fn table(mut self, signature: [u8; 4], revision: u8, body: &[u8]) -> Self {
+        self.tables.push((signature, revision, body.to_vec()));
+        self
+    }
+
+    /// Writes the tables to `memory`, which is the physical memory starting at address 0
+    /// like that of a MockKernelApi, placing the RSDP at the physical address `base`.
+    /// Returns the address of the RSDP, or None if the tables don't fit into `memory`.
+    /// `base` must be 64 byte aligned, as the FACS is placed at a 64 byte aligned offset.
+    pub fn write_to(&self, memory: &mut [u8], base: u64) -> Option<PhysAddr> {
+        debug_assert!(base % 64 == 0, "the tables must be placed at a 64 byte aligned address");
+
+        let mut fadt = unsafe {
+            core::slice::from_raw_parts(&self.fadt as *const Fadt as *const u8, size_of::<Fadt>())
+        }.to_vec();
+        let mut madt = Vec::new();
+        madt.extend_from_slice(&self.lapic_address.to_le_bytes());
+        madt.extend_from_slice(&self.madt_flags.to_le_bytes());
+        madt.extend_from_slice(&self.madt_entries);
+
+        let mut image = Layout::default();
+        let rsdp = image.reserve(36, 16);
+        let facs = image.reserve(FACS_LENGTH, 64);
+        let xsdt = image.reserve(SDT_HEADER + 8 * (2 + self.tables.len()), 8);
+        let fadt_offset = image.reserve(fadt.len(), 8);
+        let dsdt = image.reserve(SDT_HEADER + self.dsdt.len(), 8);
+        let madt_offset = image.reserve(SDT_HEADER + madt.len(), 8);
+        let tables: Vec<_> = self.tables.iter()
+            .map(|(_, _, body)| image.reserve(SDT_HEADER + body.len(), 8))
+            .collect();
+
+        let memory = memory.get_mut(usize::try_from(base).ok()?..)?.get_mut(..image.size)?;
+        memory.fill(0);
+        let addr = |offset: usize| base + offset as u64;
+
+        let mut entries = Vec::new();
+        for offset in [fadt_offset, madt_offset].iter().chain(&tables) {
+            entries.extend_from_slice(&addr(*offset).to_le_bytes());
+        }
+        write_sdt(memory, xsdt, *b"XSDT", 1, &entries);
+
+        // the 32-bit pointers stay 0 above 4GiB, uACPI uses the 64-bit ones then
+        let addr32 = |offset: usize| u32::try_from(addr(offset)).unwrap_or(0);
+        fadt[36..40].copy_from_slice(&addr32(facs).to_le_bytes());
+        fadt[40..44].copy_from_slice(&addr32(dsdt).to_le_bytes());
+        fadt[132..140].copy_from_slice(&addr(facs).to_le_bytes());
+        fadt[140..148].copy_from_slice(&addr(dsdt).to_le_bytes());
+        write_sdt(memory, fadt_offset, *b"FACP", 6, &fadt[SDT_HEADER..]);
+
+        write_sdt(memory, dsdt, *b"DSDT", 2, &self.dsdt);
+        write_sdt(memory, madt_offset, *b"APIC", 5, &madt);
+        for ((signature, revision, body), offset) in self.tables.iter().zip(tables) {
+            write_sdt(memory, offset, *signature, *revision, body);
+        }
+
+        let facs_bytes = &mut memory[facs..facs + FACS_LENGTH];
+        facs_bytes[..4].copy_from_slice(b"FACS");
+        facs_bytes[4..8].copy_from_slice(&(FACS_LENGTH as u32).to_le_bytes());
+        facs_bytes[32] = 2; // version
+
+        let rsdp_bytes = &mut memory[rsdp..rsdp + 36];
+        rsdp_bytes[..8].copy_from_slice(b"RSD PTR ");
+        rsdp_bytes[9..15].copy_from_slice(OEM_ID);
+        rsdp_bytes[15] = 2; // revision, i.e. ACPI 2.0+ with an XSDT
+        rsdp_bytes[20..24].copy_from_slice(&36u32.to_le_bytes());
+        rsdp_bytes[24..32].copy_from_slice(&addr(xsdt).to_le_bytes());
+        rsdp_bytes[8] = checksum(&rsdp_bytes[..20]);
+        rsdp_bytes[32] = checksum(rsdp_bytes);
+
+        PhysAddr::try_new(addr(rsdp)).ok()
+    }
+}
+
+// the offsets the FADT pointers are patched at
+const _: () = {
+    use core::mem::offset_of;
+    assert!(offset_of!(Fadt, firmware_ctrl) == 36);
+    assert!(offset_of!(Fadt, dsdt) == 40);
+    assert!(offset_of!(Fadt, x_firmware_ctrl) == 132);
+    assert!(offset_of!(Fadt, x_dsdt) == 140);
+};
+
+/// Hands out the offsets of the tables within the image.
+#[derive(Default)]
+struct Layout {
+    size: usize,
+}
+
+impl Layout {
+    fn reserve(&mut self, len: usize, align: usize) -> usize {
+        let offset = self.size.next_multiple_of(align);
+        self.size = offset + len;
+        offset
+    }
+}
+
+/// Writes a table with a header and `body` at `offset`, with the checksum filled in.
+fn write_sdt(memory: &mut [u8], offset: usize, signature: [u8; 4], revision: u8, body: &[u8]) {
+    let length = SDT_HEADER + body.len();
+    let table = &mut memory[offset..offset + length];
+    table[..4].copy_from_slice(&signature);
+    table[4..8].copy_from_slice(&(length as u32).to_le_bytes());
+    table[8] = revision;
+    table[10..16].copy_from_slice(OEM_ID);
+    table[16..24].copy_from_slice(OEM_TABLE_ID);
+    table[24..28].copy_from_slice(&1u32.to_le_bytes());
+    table[28..32].copy_from_slice(b"RUST");
+    table[32..36].copy_from_slice(&1u32.to_le_bytes());
+    table[SDT_HEADER..].copy_from_slice(body);
+    table[9] = checksum(table);
+}
+
+/// Returns the byte that makes `bytes` sum up to 0, assuming its checksum field is 0.
+fn checksum(bytes: &[u8]) -> u8 {
+    0u8.wrapping_sub(bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use super::*;
+    use crate::{madt_entries_from_bytes, xsdt_entries_from_bytes, MadtEntry};
+
+    /// Returns the bytes of the table at `addr`, as long as its header says.
+    fn table_at(memory: &[u8], addr: u64) -> &[u8] {
+        let table = &memory[addr as usize..];
+        let length = u32::from_le_bytes(table[4..8].try_into().unwrap());
+        &table[..length as usize]
+    }
+
+    fn sum(bytes: &[u8]) -> u8 {
+        bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+    }
+
+    #[test]
+    fn write_and_parse() {
+        let tables = SyntheticTables::new()
+            .dsdt(&[0x10, 0x05, 0x5C, 0x00])
+            .lapic(0, 1, 1)
+            .ioapic(2, 0xFEC0_0000, 0)
+            .irq_source_override(9, 20, 0xD)
+            .table(*b"SSDT", 2, &[0xA3]);
+        let mut memory = vec![0; 0x2000];
+        let rsdp = tables.write_to(&mut memory, 0x1000).unwrap().as_u64();
+        assert_eq!(rsdp, 0x1000);
+
+        let rsdp = &memory[rsdp as usize..rsdp as usize + 36];
+        assert_eq!(&rsdp[..8], b"RSD PTR ");
+        assert_eq!(sum(&rsdp[..20]), 0);
+        assert_eq!(sum(rsdp), 0);
+
+        let xsdt = table_at(&memory, u64::from_le_bytes(rsdp[24..32].try_into().unwrap()));
+        assert_eq!(&xsdt[..4], b"XSDT");
+        assert_eq!(sum(xsdt), 0);
+
+        let entries: Vec<_> = xsdt_entries_from_bytes(xsdt).map(PhysAddr::as_u64).collect();
+        assert_eq!(entries.len(), 3);
+        let signatures: Vec<_> = entries.iter()
+            .map(|addr| {
+                let table = table_at(&memory, *addr);
+                assert_eq!(sum(table), 0);
+                &table[..4]
+            })
+            .collect();
+        assert_eq!(signatures, [b"FACP", b"APIC", b"SSDT"]);
+
+        let fadt = table_at(&memory, entries[0]);
+        let dsdt = table_at(&memory, u64::from_le_bytes(fadt[140..148].try_into().unwrap()));
+        assert_eq!(&dsdt[..4], b"DSDT");
+        assert_eq!(sum(dsdt), 0);
+        assert_eq!(&dsdt[SDT_HEADER..], [0x10, 0x05, 0x5C, 0x00]);
+
+        let madt = table_at(&memory, entries[1]);
+        let madt: Vec<_> = madt_entries_from_bytes(madt).collect();
+        assert_eq!(madt.len(), 3);
+        assert!(matches!(madt[0], MadtEntry::Lapic(lapic) if lapic.uid == 0 && lapic.id == 1));
+        assert!(matches!(madt[1], MadtEntry::Ioapic(ioapic) if ioapic.id == 2));
+        match madt[2] {
+            MadtEntry::IrqSourceOverride(iso) => {
+                assert_eq!(iso.source, 9);
+                assert_eq!(u32::from_le(iso.gsi), 20);
+                assert_eq!(u16::from_le(iso.flags), 0xD);
+            }
+            entry => panic!("unexpected MADT entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn too_small() {
+        let mut memory = vec![0; 0x1100];
+        assert!(SyntheticTables::new().write_to(&mut memory, 0x1000).is_none());
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+use core::ffi::CStr;
+use core::fmt::{self, Display};
+use crate::{eval, eval_simple_integer, NamespaceNode, NsIterDecision, ObjectType, Status};
+
+/// A temperature in tenths of a Kelvin, the unit of all thermal zone objects.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeciKelvin(pub u64);
+
+impl DeciKelvin {
+    /// Converts the temperature to whole degrees Celsius, rounding towards zero.
+    pub fn to_celsius(self) -> i64 {
+        self.to_millicelsius() / 1000
+    }
+
+    /// Converts the temperature to thousandths of a degree Celsius.
+    pub fn to_millicelsius(self) -> i64 {
+        self.0 as i64 * 100 - 273_150
+    }
+}
+
+impl Display for DeciKelvin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millicelsius = self.to_millicelsius();
+        let sign = if millicelsius < 0 { "-" } else { "" };
+        let millicelsius = millicelsius.unsigned_abs();
+        write!(f, "{}{}.{}°C", sign, millicelsius / 1000, millicelsius % 1000 / 100)
+    }
+}
+
+/// An entry of _TRT, describing how much the `source` device heats up the `target` device.
+#[derive(Debug)]
+pub struct ThermalRelationship {
+    pub source: NamespaceNode,
+    pub target: NamespaceNode,
+    /// The relative influence of `source` on `target`, higher means more.
+    pub influence: u64,
+    /// The sampling period of `target` in tenths of a second.
+    pub sampling_period: u64,
+}
+
+/// A thermal zone, i.e. a ThermalZone object in the namespace.
+#[derive(Debug)]
+pub struct ThermalZone(NamespaceNode);
+
+impl ThermalZone {
+    /// Wraps `node`, returns TypeMismatch if it's not a thermal zone.
+    pub fn new(node: NamespaceNode) -> Result<Self, Status> {
+        match node.object_type()? {
+            ObjectType::ThermalZone => Ok(Self(node)),
+            _ => Err(Status::TypeMismatch),
+        }
+    }
+
+    /// Collects all the thermal zones in the namespace.
+    pub fn find_all() -> Result<Vec<ThermalZone>, Status> {
+        let mut zones = Vec::new();
+        NamespaceNode::root().for_each_child(|node| {
+            if let Ok(ObjectType::ThermalZone) = node.object_type() {
+                zones.push(ThermalZone(NamespaceNode(node.0)));
+            }
+            NsIterDecision::Continue
+        })?;
+        Ok(zones)
+    }
+
+    pub fn node(&self) -> &NamespaceNode {
+        &self.0
+    }
+
+    fn temperature_of(&self, method: &CStr) -> Result<DeciKelvin, Status> {
+        eval_simple_integer(&self.0, method).map(DeciKelvin)
+    }
+
+    /// Evaluates _TMP, the current temperature of the zone.
+    pub fn temperature(&self) -> Result<DeciKelvin, Status> {
+        self.temperature_of(c"_TMP")
+    }
+
+    /// Evaluates _CRT, the temperature at which the OS must shut down the system.
+    pub fn critical_trip(&self) -> Result<DeciKelvin, Status> {
+        self.temperature_of(c"_CRT")
+    }
+
+    /// Evaluates _HOT, the temperature at which the OS should hibernate the system.
+    pub fn hot_trip(&self) -> Result<DeciKelvin, Status> {
+        self.temperature_of(c"_HOT")
+    }
+
+    /// Evaluates _PSV, the temperature at which passive cooling, i.e. throttling, starts.
+    pub fn passive_trip(&self) -> Result<DeciKelvin, Status> {
+        self.temperature_of(c"_PSV")
+    }
+
+    /// Evaluates _ACx, the temperature at which active cooling level `level` (0 to 9) starts,
+    /// where 0 is the highest temperature and the most cooling.
+    pub fn active_trip(&self, level: u8) -> Result<DeciKelvin, Status> {
+        if level > 9 {
+            return Err(Status::InvalidArgument);
+        }
+        let method = [b'_', b'A', b'C', b'0' + level, 0];
+        self.temperature_of(CStr::from_bytes_with_nul(&method).unwrap())
+    }
+
+    /// Evaluates _TZP, the recommended polling interval in tenths of a second.
+    /// Returns 0 if the zone notifies the OS of temperature changes and need not be polled.
+    pub fn polling_interval(&self) -> Result<u64, Status> {
+        eval_simple_integer(&self.0, c"_TZP")
+    }
+
+    /// Evaluates _TSP, the sampling period for passive cooling in tenths of a second.
+    pub fn passive_sampling_period(&self) -> Result<u64, Status> {
+        eval_simple_integer(&self.0, c"_TSP")
+    }
+
+    /// Evaluates and decodes _TRT, the thermal relationship table of the zone.
+    pub fn relationships(&self) -> Result<Vec<ThermalRelationship>, Status> {
+        let trt = eval(&self.0, c"_TRT", [])?;
+        trt.get_package()
+            .ok_or(Status::TypeMismatch)?
+            .map(|entry| {
+                let fields: Vec<_> = entry.get_package().ok_or(Status::TypeMismatch)?.collect();
+                if fields.len() < 4 {
+                    return Err(Status::TypeMismatch);
+                }
+                Ok(ThermalRelationship {
+                    source: fields[0].resolve_as_node(&self.0)?,
+                    target: fields[1].resolve_as_node(&self.0)?,
+                    influence: fields[2].get_int().ok_or(Status::TypeMismatch)?,
+                    sampling_period: fields[3].get_int().ok_or(Status::TypeMismatch)?,
+                })
+            })
+            .collect()
+    }
+}
@@ -1,6 +1,10 @@
+use alloc::ffi::CString;
+use alloc::vec::Vec;
 use core::ffi::CStr;
-use core::fmt::Debug;
+use core::fmt::{Debug, Display};
+use core::hash::{Hash, Hasher};
 use core::slice;
+use crate::{table_find_by_signature, NamespaceNode, NsIterDecision, DSDT_SIGNATURE};
 
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -12,6 +16,18 @@ impl LogLevel {
     pub const INFO: LogLevel = LogLevel(uacpi_sys::UACPI_LOG_INFO);
     pub const WARN: LogLevel = LogLevel(uacpi_sys::UACPI_LOG_WARN);
     pub const ERROR: LogLevel = LogLevel(uacpi_sys::UACPI_LOG_ERROR);
+
+    /// Converts a uacpi_log_level, None if the level is not one of the constants above.
+    pub fn from_raw(log_level: uacpi_sys::uacpi_log_level) -> Option<Self> {
+        match log_level {
+            uacpi_sys::UACPI_LOG_DEBUG => Some(LogLevel::DEBUG),
+            uacpi_sys::UACPI_LOG_TRACE => Some(LogLevel::TRACE),
+            uacpi_sys::UACPI_LOG_INFO => Some(LogLevel::INFO),
+            uacpi_sys::UACPI_LOG_WARN => Some(LogLevel::WARN),
+            uacpi_sys::UACPI_LOG_ERROR => Some(LogLevel::ERROR),
+            _ => None,
+        }
+    }
 }
 
 #[repr(transparent)]
@@ -38,23 +54,101 @@ impl Handle {
     }
 }
 
+// A handle is an opaque value handed out by the kernel, it is never dereferenced by this crate
+// or uACPI and may be used from any CPU.
+unsafe impl Send for Handle {}
+unsafe impl Sync for Handle {}
+
 impl Debug for Handle {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:#x}", self.as_u64())
     }
 }
 
+macro_rules! typed_handle {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+        pub struct $name(Handle);
+
+        impl $name {
+            /// Creates a new typed kernel handle. Using 0 here is not allowed.
+            pub fn new(handle: u64) -> Self {
+                Self(Handle::new(handle))
+            }
+
+            pub fn as_u64(self) -> u64 {
+                self.0.as_u64()
+            }
+        }
+
+        impl From<Handle> for $name {
+            fn from(handle: Handle) -> Self {
+                Self(handle)
+            }
+        }
+
+        impl From<$name> for Handle {
+            fn from(handle: $name) -> Self {
+                handle.0
+            }
+        }
+
+        impl core::ops::Deref for $name {
+            type Target = Handle;
+
+            fn deref(&self) -> &Handle {
+                &self.0
+            }
+        }
+    };
+}
+
+typed_handle!(
+    /// A handle to a kernel mutex created by KernelApi::create_mutex.
+    MutexHandle
+);
+typed_handle!(
+    /// A handle to a kernel event created by KernelApi::create_event.
+    EventHandle
+);
+typed_handle!(
+    /// A handle to a kernel spinlock created by KernelApi::create_spinlock.
+    SpinlockHandle
+);
+
 #[repr(transparent)]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct PhysAddr(pub(crate) uacpi_sys::uacpi_phys_addr);
 
 impl PhysAddr {
+    /// Creates a new physical address.
+    /// Panics if it doesn't fit into the physical address width uACPI was built with.
     pub fn new(phys_addr: u64) -> PhysAddr {
-        PhysAddr(phys_addr as _)
+        match PhysAddr::try_new(phys_addr) {
+            Ok(addr) => addr,
+            Err(_) => panic!("physical address {:#x} is too wide for this uACPI build", phys_addr),
+        }
+    }
+
+    /// Creates a new physical address, returns InvalidArgument
+    /// if it doesn't fit into the physical address width uACPI was built with.
+    pub fn try_new(phys_addr: u64) -> Result<PhysAddr, Status> {
+        match uacpi_sys::uacpi_phys_addr::try_from(phys_addr) {
+            Ok(addr) => Ok(PhysAddr(addr)),
+            Err(_) => Err(Status::InvalidArgument),
+        }
     }
 
     pub fn as_u64(self) -> u64 {
-        self.0
+        self.0 as _
+    }
+}
+
+impl Debug for PhysAddr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#x}", self.as_u64())
     }
 }
 
@@ -68,6 +162,10 @@ impl ThreadId {
     }
 }
 
+// A thread id is only ever compared, never dereferenced, so it can be shared across CPUs.
+unsafe impl Send for ThreadId {}
+unsafe impl Sync for ThreadId {}
+
 #[repr(transparent)]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
 pub struct CpuFlags(pub(crate) uacpi_sys::uacpi_cpu_flags);
@@ -87,29 +185,52 @@ pub enum InitLevel {
     NamespaceInitialized = 3,
 }
 
-impl From<uacpi_sys::uacpi_init_level> for InitLevel {
-    fn from(level: uacpi_sys::uacpi_init_level) -> Self {
+impl InitLevel {
+    /// Converts a uacpi_init_level, None if the level is not known to this crate.
+    pub fn from_raw(level: uacpi_sys::uacpi_init_level) -> Option<Self> {
         match level {
-            uacpi_sys::UACPI_INIT_LEVEL_EARLY => InitLevel::Early,
-            uacpi_sys::UACPI_INIT_LEVEL_SUBSYSTEM_INITIALIZED => InitLevel::SubsystemInitialized,
-            uacpi_sys::UACPI_INIT_LEVEL_NAMESPACE_LOADED => InitLevel::NamespaceLoaded,
-            uacpi_sys::UACPI_INIT_LEVEL_NAMESPACE_INITIALIZED => InitLevel::NamespaceInitialized,
-            _ => panic!("Unknown uacpi_init_level value: {:#x}", level),
+            uacpi_sys::UACPI_INIT_LEVEL_EARLY => Some(InitLevel::Early),
+            uacpi_sys::UACPI_INIT_LEVEL_SUBSYSTEM_INITIALIZED => Some(InitLevel::SubsystemInitialized),
+            uacpi_sys::UACPI_INIT_LEVEL_NAMESPACE_LOADED => Some(InitLevel::NamespaceLoaded),
+            uacpi_sys::UACPI_INIT_LEVEL_NAMESPACE_INITIALIZED => Some(InitLevel::NamespaceInitialized),
+            _ => None,
         }
     }
 }
 
+/// Levels unknown to this crate are treated as Early, so require_init_level fails
+/// instead of letting through calls that depend on a level that might not be reached yet.
+impl From<uacpi_sys::uacpi_init_level> for InitLevel {
+    fn from(level: uacpi_sys::uacpi_init_level) -> Self {
+        InitLevel::from_raw(level).unwrap_or(InitLevel::Early)
+    }
+}
+
 #[repr(transparent)]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct IOAddr(pub(crate) uacpi_sys::uacpi_io_addr);
 
 impl IOAddr {
+    /// Creates a new io address.
+    /// Panics if it doesn't fit into the io address width uACPI was built with.
     pub fn new(phys_addr: u64) -> IOAddr {
-        IOAddr(phys_addr as _)
+        match IOAddr::try_new(phys_addr) {
+            Ok(addr) => addr,
+            Err(_) => panic!("io address {:#x} is too wide for this uACPI build", phys_addr),
+        }
+    }
+
+    /// Creates a new io address, returns InvalidArgument
+    /// if it doesn't fit into the io address width uACPI was built with.
+    pub fn try_new(phys_addr: u64) -> Result<IOAddr, Status> {
+        match uacpi_sys::uacpi_io_addr::try_from(phys_addr) {
+            Ok(addr) => Ok(IOAddr(addr)),
+            Err(_) => Err(Status::InvalidArgument),
+        }
     }
 
     pub fn as_u64(self) -> u64 {
-        self.0
+        self.0 as _
     }
 }
 
@@ -118,6 +239,15 @@ impl IOAddr {
 pub struct PCIAddress(pub(crate) uacpi_sys::uacpi_pci_address);
 
 impl PCIAddress {
+    pub fn new(segment: u16, bus: u8, device: u8, function: u8) -> Self {
+        Self(uacpi_sys::uacpi_pci_address {
+            segment,
+            bus,
+            device,
+            function,
+        })
+    }
+
     pub fn segment(&self) -> u16 {
         self.0.segment
     }
@@ -135,6 +265,21 @@ impl PCIAddress {
     }
 }
 
+impl PartialEq for PCIAddress {
+    fn eq(&self, other: &Self) -> bool {
+        (self.segment(), self.bus(), self.device(), self.function())
+            == (other.segment(), other.bus(), other.device(), other.function())
+    }
+}
+
+impl Eq for PCIAddress {}
+
+impl Hash for PCIAddress {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.segment(), self.bus(), self.device(), self.function()).hash(state)
+    }
+}
+
 impl Debug for PCIAddress {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
@@ -170,6 +315,8 @@ pub enum Status {
     NoResourceEndTag = uacpi_sys::UACPI_STATUS_NO_RESOURCE_END_TAG,
     CompiledOut = uacpi_sys::UACPI_STATUS_COMPILED_OUT,
     HardwareTimeout = uacpi_sys::UACPI_STATUS_HARDWARE_TIMEOUT,
+    /// A wait ran out before the awaited object became available, e.g. the global lock.
+    Timeout = uacpi_sys::UACPI_STATUS_TIMEOUT,
     AmlUndefinedReference = uacpi_sys::UACPI_STATUS_AML_UNDEFINED_REFERENCE,
     AmlInvalidNamestring = uacpi_sys::UACPI_STATUS_AML_INVALID_NAMESTRING,
     AmlObjectAlreadyExists = uacpi_sys::UACPI_STATUS_AML_OBJECT_ALREADY_EXISTS,
@@ -177,65 +324,126 @@ pub enum Status {
     AmlIncompatibleObjectType = uacpi_sys::UACPI_STATUS_AML_INCOMPATIBLE_OBJECT_TYPE,
     AmlBadEncoding = uacpi_sys::UACPI_STATUS_AML_BAD_ENCODING,
     AmlOutOfBoundsIndex = uacpi_sys::UACPI_STATUS_AML_OUT_OF_BOUNDS_INDEX,
+    /// The AML acquired a mutex with a lower sync level than one it holds, see eval.
     AmlSyncLevelTooHigh = uacpi_sys::UACPI_STATUS_AML_SYNC_LEVEL_TOO_HIGH,
     AmlInvalidResource = uacpi_sys::UACPI_STATUS_AML_INVALID_RESOURCE,
     AmlLoopTimeout = uacpi_sys::UACPI_STATUS_AML_LOOP_TIMEOUT,
 }
 
-impl From<uacpi_sys::uacpi_status> for Status {
-    fn from(status: uacpi_sys::uacpi_status) -> Self {
+impl Status {
+    /// Returns the uacpi_status code of this status.
+    pub fn as_raw(self) -> u32 {
+        self as u32
+    }
+
+    /// Converts a uacpi_status code, e.g. one that was passed through a syscall,
+    /// None if the code is not known to this crate.
+    pub fn from_raw(status: uacpi_sys::uacpi_status) -> Option<Self> {
         match status {
-            uacpi_sys::UACPI_STATUS_OK => Status::Ok,
-            uacpi_sys::UACPI_STATUS_MAPPING_FAILED => Status::MappingFailed,
-            uacpi_sys::UACPI_STATUS_OUT_OF_MEMORY => Status::OutOfMemory,
-            uacpi_sys::UACPI_STATUS_BAD_CHECKSUM => Status::BadChecksum,
-            uacpi_sys::UACPI_STATUS_INVALID_SIGNATURE => Status::InvalidSignature,
-            uacpi_sys::UACPI_STATUS_INVALID_TABLE_LENGTH => Status::InvalidTableLenght,
-            uacpi_sys::UACPI_STATUS_NOT_FOUND => Status::NotFound,
-            uacpi_sys::UACPI_STATUS_INVALID_ARGUMENT => Status::InvalidArgument,
-            uacpi_sys::UACPI_STATUS_UNIMPLEMENTED => Status::Unimplemented,
-            uacpi_sys::UACPI_STATUS_ALREADY_EXISTS => Status::AlreadyExists,
-            uacpi_sys::UACPI_STATUS_INTERNAL_ERROR => Status::InternalError,
-            uacpi_sys::UACPI_STATUS_TYPE_MISMATCH => Status::TypeMismatch,
-            uacpi_sys::UACPI_STATUS_INIT_LEVEL_MISMATCH => Status::InitLevelMismatch,
+            uacpi_sys::UACPI_STATUS_OK => Some(Status::Ok),
+            uacpi_sys::UACPI_STATUS_MAPPING_FAILED => Some(Status::MappingFailed),
+            uacpi_sys::UACPI_STATUS_OUT_OF_MEMORY => Some(Status::OutOfMemory),
+            uacpi_sys::UACPI_STATUS_BAD_CHECKSUM => Some(Status::BadChecksum),
+            uacpi_sys::UACPI_STATUS_INVALID_SIGNATURE => Some(Status::InvalidSignature),
+            uacpi_sys::UACPI_STATUS_INVALID_TABLE_LENGTH => Some(Status::InvalidTableLenght),
+            uacpi_sys::UACPI_STATUS_NOT_FOUND => Some(Status::NotFound),
+            uacpi_sys::UACPI_STATUS_INVALID_ARGUMENT => Some(Status::InvalidArgument),
+            uacpi_sys::UACPI_STATUS_UNIMPLEMENTED => Some(Status::Unimplemented),
+            uacpi_sys::UACPI_STATUS_ALREADY_EXISTS => Some(Status::AlreadyExists),
+            uacpi_sys::UACPI_STATUS_INTERNAL_ERROR => Some(Status::InternalError),
+            uacpi_sys::UACPI_STATUS_TYPE_MISMATCH => Some(Status::TypeMismatch),
+            uacpi_sys::UACPI_STATUS_INIT_LEVEL_MISMATCH => Some(Status::InitLevelMismatch),
             uacpi_sys::UACPI_STATUS_NAMESPACE_NODE_DANGLING => {
-                Status::NamespaceNodeDangling
+                Some(Status::NamespaceNodeDangling)
             }
-            uacpi_sys::UACPI_STATUS_NO_HANDLER => Status::NoHandler,
-            uacpi_sys::UACPI_STATUS_NO_RESOURCE_END_TAG => Status::NoResourceEndTag,
-            uacpi_sys::UACPI_STATUS_COMPILED_OUT => Status::CompiledOut,
-            uacpi_sys::UACPI_STATUS_HARDWARE_TIMEOUT => Status::HardwareTimeout,
+            uacpi_sys::UACPI_STATUS_NO_HANDLER => Some(Status::NoHandler),
+            uacpi_sys::UACPI_STATUS_NO_RESOURCE_END_TAG => Some(Status::NoResourceEndTag),
+            uacpi_sys::UACPI_STATUS_COMPILED_OUT => Some(Status::CompiledOut),
+            uacpi_sys::UACPI_STATUS_HARDWARE_TIMEOUT => Some(Status::HardwareTimeout),
+            uacpi_sys::UACPI_STATUS_TIMEOUT => Some(Status::Timeout),
             uacpi_sys::UACPI_STATUS_AML_UNDEFINED_REFERENCE => {
-                Status::AmlUndefinedReference
+                Some(Status::AmlUndefinedReference)
             }
             uacpi_sys::UACPI_STATUS_AML_INVALID_NAMESTRING => {
-                Status::AmlInvalidNamestring
+                Some(Status::AmlInvalidNamestring)
             }
             uacpi_sys::UACPI_STATUS_AML_OBJECT_ALREADY_EXISTS => {
-                Status::AmlObjectAlreadyExists
+                Some(Status::AmlObjectAlreadyExists)
             }
-            uacpi_sys::UACPI_STATUS_AML_INVALID_OPCODE => Status::AmlInvalidOpcode,
+            uacpi_sys::UACPI_STATUS_AML_INVALID_OPCODE => Some(Status::AmlInvalidOpcode),
             uacpi_sys::UACPI_STATUS_AML_INCOMPATIBLE_OBJECT_TYPE => {
-                Status::AmlIncompatibleObjectType
+                Some(Status::AmlIncompatibleObjectType)
             }
-            uacpi_sys::UACPI_STATUS_AML_BAD_ENCODING => Status::AmlBadEncoding,
+            uacpi_sys::UACPI_STATUS_AML_BAD_ENCODING => Some(Status::AmlBadEncoding),
             uacpi_sys::UACPI_STATUS_AML_OUT_OF_BOUNDS_INDEX => {
-                Status::AmlOutOfBoundsIndex
+                Some(Status::AmlOutOfBoundsIndex)
             }
             uacpi_sys::UACPI_STATUS_AML_SYNC_LEVEL_TOO_HIGH => {
-                Status::AmlSyncLevelTooHigh
+                Some(Status::AmlSyncLevelTooHigh)
             }
-            uacpi_sys::UACPI_STATUS_AML_INVALID_RESOURCE => Status::AmlInvalidResource,
-            uacpi_sys::UACPI_STATUS_AML_LOOP_TIMEOUT => Status::AmlLoopTimeout,
-            _ => panic!("Unknown uacpi_status value: {:#x}", status),
+            uacpi_sys::UACPI_STATUS_AML_INVALID_RESOURCE => Some(Status::AmlInvalidResource),
+            uacpi_sys::UACPI_STATUS_AML_LOOP_TIMEOUT => Some(Status::AmlLoopTimeout),
+            _ => None,
         }
     }
 }
 
+/// Codes unknown to this crate, e.g. added by a newer uACPI, become InternalError
+/// so they can still be handled as a failure, use from_raw to tell them apart.
+impl From<uacpi_sys::uacpi_status> for Status {
+    fn from(status: uacpi_sys::uacpi_status) -> Self {
+        Self::from_raw(status).unwrap_or(Status::InternalError)
+    }
+}
+
 #[derive(Debug)]
 pub enum FirmwareRequest {
+    /// The AML executed a Breakpoint, meant for attaching a debugger.
     Breakpoint { context: Handle },
+    /// The AML executed Fatal, see FatalError.
     Fatal { typ: u8, code: u32, arg: u64 },
+    /// A request type this crate does not know about yet.
+    Unknown { typ: u8 },
+}
+
+impl FirmwareRequest {
+    /// Returns the details of a Fatal request.
+    pub fn as_fatal(&self) -> Option<FatalError> {
+        match *self {
+            FirmwareRequest::Fatal { typ, code, arg } => Some(FatalError { typ, code, arg }),
+            _ => None,
+        }
+    }
+}
+
+/// A fatal error reported by the AML Fatal operator.
+/// The spec leaves the meaning of all three values to the OEM, there are no standard types
+/// to decode, so the OS has to decide from the platform whether to log the error and continue
+/// or halt. The spec expects it to log the error and shut down in a timely fashion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FatalError {
+    /// The OEM defined type of the error.
+    pub typ: u8,
+    /// The OEM defined error code.
+    pub code: u32,
+    /// An OEM defined argument, e.g. the address of the failing component.
+    pub arg: u64,
+}
+
+/// What to do after the AML executed Fatal, returned by KernelApi::fatal_error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatalAction {
+    /// Return to the AML and let it continue.
+    Continue,
+    /// Stop the machine by calling KernelApi::halt.
+    Halt,
+}
+
+impl Display for FatalError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "firmware fatal error: type {:#04x}, code {:#010x}, argument {:#x}",
+            self.typ, self.code, self.arg)
+    }
 }
 
 impl From<uacpi_sys::uacpi_firmware_request> for FirmwareRequest {
@@ -253,7 +461,7 @@ impl From<uacpi_sys::uacpi_firmware_request> for FirmwareRequest {
                     arg: unsafe { value.__bindgen_anon_1.fatal.arg },
                 }
             }
-            _ => panic!("Unknown uacpi_firmware_request_type: {:#x}", value.type_),
+            _ => FirmwareRequest::Unknown { typ: value.type_ },
         }
     }
 }
@@ -275,6 +483,84 @@ impl From<uacpi_sys::uacpi_work_type> for WorkType {
     }
 }
 
+/// Whether an interrupt handler serviced the interrupt.
+/// Handlers on a shared line return NotHandled if the interrupt wasn't raised by their device.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptRet {
+    NotHandled = uacpi_sys::UACPI_INTERRUPT_NOT_HANDLED,
+    Handled = uacpi_sys::UACPI_INTERRUPT_HANDLED,
+}
+
+impl From<uacpi_sys::uacpi_interrupt_ret> for InterruptRet {
+    fn from(value: uacpi_sys::uacpi_interrupt_ret) -> Self {
+        if value & uacpi_sys::UACPI_INTERRUPT_HANDLED != 0 {
+            InterruptRet::Handled
+        } else {
+            InterruptRet::NotHandled
+        }
+    }
+}
+
+// These are passed to and returned from the kernel api shims by value in place of the C types.
+const _: () = {
+    use core::mem::size_of;
+    assert!(size_of::<Status>() == size_of::<uacpi_sys::uacpi_status>());
+    assert!(size_of::<WorkType>() == size_of::<uacpi_sys::uacpi_work_type>());
+    assert!(size_of::<Handle>() == size_of::<uacpi_sys::uacpi_handle>());
+    assert!(size_of::<ThreadId>() == size_of::<uacpi_sys::uacpi_thread_id>());
+    assert!(size_of::<CpuFlags>() == size_of::<uacpi_sys::uacpi_cpu_flags>());
+};
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectType {
+    Uninitialized = uacpi_sys::UACPI_OBJECT_UNINITIALIZED,
+    Integer = uacpi_sys::UACPI_OBJECT_INTEGER,
+    String = uacpi_sys::UACPI_OBJECT_STRING,
+    Buffer = uacpi_sys::UACPI_OBJECT_BUFFER,
+    Package = uacpi_sys::UACPI_OBJECT_PACKAGE,
+    FieldUnit = uacpi_sys::UACPI_OBJECT_FIELD_UNIT,
+    Device = uacpi_sys::UACPI_OBJECT_DEVICE,
+    Event = uacpi_sys::UACPI_OBJECT_EVENT,
+    Method = uacpi_sys::UACPI_OBJECT_METHOD,
+    Mutex = uacpi_sys::UACPI_OBJECT_MUTEX,
+    OperationRegion = uacpi_sys::UACPI_OBJECT_OPERATION_REGION,
+    PowerResource = uacpi_sys::UACPI_OBJECT_POWER_RESOURCE,
+    Processor = uacpi_sys::UACPI_OBJECT_PROCESSOR,
+    ThermalZone = uacpi_sys::UACPI_OBJECT_THERMAL_ZONE,
+    BufferField = uacpi_sys::UACPI_OBJECT_BUFFER_FIELD,
+    Debug = uacpi_sys::UACPI_OBJECT_DEBUG,
+    Reference = uacpi_sys::UACPI_OBJECT_REFERENCE,
+    BufferIndex = uacpi_sys::UACPI_OBJECT_BUFFER_INDEX,
+}
+
+impl ObjectType {
+    pub(crate) fn from_raw(typ: uacpi_sys::uacpi_object_type) -> Option<Self> {
+        Some(match typ {
+            uacpi_sys::UACPI_OBJECT_UNINITIALIZED => Self::Uninitialized,
+            uacpi_sys::UACPI_OBJECT_INTEGER => Self::Integer,
+            uacpi_sys::UACPI_OBJECT_STRING => Self::String,
+            uacpi_sys::UACPI_OBJECT_BUFFER => Self::Buffer,
+            uacpi_sys::UACPI_OBJECT_PACKAGE => Self::Package,
+            uacpi_sys::UACPI_OBJECT_FIELD_UNIT => Self::FieldUnit,
+            uacpi_sys::UACPI_OBJECT_DEVICE => Self::Device,
+            uacpi_sys::UACPI_OBJECT_EVENT => Self::Event,
+            uacpi_sys::UACPI_OBJECT_METHOD => Self::Method,
+            uacpi_sys::UACPI_OBJECT_MUTEX => Self::Mutex,
+            uacpi_sys::UACPI_OBJECT_OPERATION_REGION => Self::OperationRegion,
+            uacpi_sys::UACPI_OBJECT_POWER_RESOURCE => Self::PowerResource,
+            uacpi_sys::UACPI_OBJECT_PROCESSOR => Self::Processor,
+            uacpi_sys::UACPI_OBJECT_THERMAL_ZONE => Self::ThermalZone,
+            uacpi_sys::UACPI_OBJECT_BUFFER_FIELD => Self::BufferField,
+            uacpi_sys::UACPI_OBJECT_DEBUG => Self::Debug,
+            uacpi_sys::UACPI_OBJECT_REFERENCE => Self::Reference,
+            uacpi_sys::UACPI_OBJECT_BUFFER_INDEX => Self::BufferIndex,
+            _ => return None,
+        })
+    }
+}
+
 #[repr(transparent)]
 pub struct Object(pub(crate) *mut uacpi_sys::uacpi_object);
 
@@ -300,6 +586,119 @@ impl Object {
         }
     }
 
+    /// Creates an uninitialized object, e.g. to pass as an argument the method ignores.
+    pub fn new_uninitialized() -> Option<Self> {
+        Self::new(uacpi_sys::UACPI_OBJECT_UNINITIALIZED)
+    }
+
+    /// Creates the integer the AML Zero constant evaluates to.
+    pub fn zero() -> Option<Self> {
+        Self::new_int(0)
+    }
+
+    /// Creates the integer the AML One constant evaluates to.
+    pub fn one() -> Option<Self> {
+        Self::new_int(1)
+    }
+
+    /// Creates the integer the AML Ones constant evaluates to, which has all bits of the
+    /// integer width set. AML integers are 32 bits wide if the DSDT revision is below 2
+    /// and 64 bits otherwise, 64 bits are assumed while the DSDT is not loaded yet.
+    pub fn ones() -> Option<Self> {
+        let ones = match table_find_by_signature(DSDT_SIGNATURE) {
+            Ok(dsdt) if dsdt.header().revision() < 2 => u32::MAX as u64,
+            Ok(_) => u64::MAX,
+            Err(_) => u64::MAX,
+        };
+        Self::new_int(ones)
+    }
+
+    /// Creates a buffer object holding a copy of `data`.
+    pub fn new_buffer(data: &[u8]) -> Option<Self> {
+        unsafe {
+            let s = Self::new(
+                uacpi_sys::UACPI_OBJECT_BUFFER
+            )?;
+            if !data.is_empty() {
+                // uACPI frees the contents with uacpi_kernel_free once the object dies
+                let ptr: *mut u8 = crate::kernel_api::uacpi_kernel_alloc(data.len()).cast();
+                if ptr.is_null() {
+                    return None;
+                }
+                ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
+                let buffer = (*s.0).__bindgen_anon_1.buffer;
+                (*buffer).__bindgen_anon_1.byte_data = ptr;
+                (*buffer).size = data.len();
+            }
+            Some(s)
+        }
+    }
+
+    /// Creates a buffer object of `len` zero bytes, to be filled in place with get_buffer_mut.
+    pub fn new_buffer_zeroed(len: usize) -> Option<Self> {
+        unsafe {
+            let s = Self::new(
+                uacpi_sys::UACPI_OBJECT_BUFFER
+            )?;
+            if len != 0 {
+                let ptr: *mut u8 = crate::kernel_api::uacpi_kernel_calloc(len, 1).cast();
+                if ptr.is_null() {
+                    return None;
+                }
+                let buffer = (*s.0).__bindgen_anon_1.buffer;
+                (*buffer).__bindgen_anon_1.byte_data = ptr;
+                (*buffer).size = len;
+            }
+            Some(s)
+        }
+    }
+
+    /// Creates a string object holding a copy of `str`.
+    pub fn new_string(str: &CStr) -> Option<Self> {
+        unsafe {
+            let s = Self::new(
+                uacpi_sys::UACPI_OBJECT_STRING
+            )?;
+            // strings are stored with their null terminator, which is counted in the size
+            let data = str.to_bytes_with_nul();
+            let ptr: *mut u8 = crate::kernel_api::uacpi_kernel_alloc(data.len()).cast();
+            if ptr.is_null() {
+                return None;
+            }
+            ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
+            let buffer = (*s.0).__bindgen_anon_1.buffer;
+            (*buffer).__bindgen_anon_1.byte_data = ptr;
+            (*buffer).size = data.len();
+            Some(s)
+        }
+    }
+
+    /// Creates a package object holding a reference to each of `elements`.
+    pub fn new_package(elements: &[Object]) -> Option<Self> {
+        unsafe {
+            let s = Self::new(
+                uacpi_sys::UACPI_OBJECT_PACKAGE
+            )?;
+            if !elements.is_empty() {
+                // uACPI unrefs the elements and frees the array once the package dies
+                let size = elements.len() * core::mem::size_of::<*mut uacpi_sys::uacpi_object>();
+                let ptr: *mut *mut uacpi_sys::uacpi_object =
+                    crate::kernel_api::uacpi_kernel_alloc(size).cast();
+                if ptr.is_null() {
+                    return None;
+                }
+                for (i, obj) in elements.iter().enumerate() {
+                    uacpi_sys::uacpi_object_ref(obj.0);
+                    ptr.add(i).write(obj.0);
+                }
+                let package = (*s.0).__bindgen_anon_1.package;
+                (*package).objects = ptr;
+                (*package).count = elements.len();
+            }
+            Some(s)
+        }
+    }
+
     pub fn get_int(&self) -> Option<u64> {
         unsafe {
             if (*self.0).type_ != uacpi_sys::UACPI_OBJECT_INTEGER as u8 {
@@ -325,36 +724,197 @@ impl Object {
         }
     }
 
+    /// Returns the contents of a buffer object for modification in place.
+    /// Returns None if the object is not a buffer or if it's shared, e.g. with a clone
+    /// or the namespace, since the other holders would observe the modification.
+    pub fn get_buffer_mut(&mut self) -> Option<&mut [u8]> {
+        unsafe {
+            if (*self.0).type_ != uacpi_sys::UACPI_OBJECT_BUFFER as u8
+                || (*self.0).shareable.reference_count != 1 {
+                return None;
+            }
+            let buffer = (*self.0).__bindgen_anon_1.buffer;
+            if (*buffer).shareable.reference_count != 1 {
+                return None;
+            }
+            if (*buffer).size == 0 {
+                return Some(&mut []);
+            }
+            Some(slice::from_raw_parts_mut(
+                (*buffer).__bindgen_anon_1.byte_data,
+                (*buffer).size
+            ))
+        }
+    }
+
     pub fn get_string(&self) -> Option<&CStr> {
+        self.string_bytes().map(|bytes| CStr::from_bytes_with_nul(bytes).unwrap())
+    }
+
+    /// Returns the bytes of a string object including the null terminator,
+    /// without checking that it's the only one.
+    fn string_bytes(&self) -> Option<&[u8]> {
         unsafe {
             if (*self.0).type_ != uacpi_sys::UACPI_OBJECT_STRING as u8 {
                 None
             } else {
                 let buffer = (*self.0).__bindgen_anon_1.buffer;
-                let slice = slice::from_raw_parts(
+                if (*buffer).size == 0 {
+                    return Some(&[]);
+                }
+                Some(slice::from_raw_parts(
                     (*buffer).__bindgen_anon_1.byte_data,
                     (*buffer).size
-                );
-                Some(CStr::from_bytes_with_nul(slice).unwrap())
+                ))
             }
         }
     }
 
-    pub fn get_package(&self) -> Option<impl Iterator<Item=Self>> {
+    /// Returns the elements of a package in order, None if this is not a package.
+    /// The iterator knows its length, so `enumerate` yields the position of each element.
+    pub fn get_package(&self) -> Option<impl ExactSizeIterator<Item=Self> + DoubleEndedIterator> {
+        let (objects, count) = self.package_raw()?;
+        let elements = unsafe { slice::from_raw_parts(objects, count) };
+        Some(elements.iter().map(|obj| Self::from_element(*obj)))
+    }
+
+    /// Returns the number of elements of a package, None if this is not a package.
+    pub fn package_len(&self) -> Option<usize> {
+        self.package_raw().map(|(_, count)| count)
+    }
+
+    /// Returns the element at `index` of a package, None if this is not a package
+    /// or `index` is out of range. Useful for packages where every position has a
+    /// defined meaning, such as _BIF.
+    pub fn package_get(&self, index: usize) -> Option<Self> {
+        let (objects, count) = self.package_raw()?;
+        (index < count).then(|| Self::from_element(unsafe { *objects.add(index) }))
+    }
+
+    fn package_raw(&self) -> Option<(*mut *mut uacpi_sys::uacpi_object, usize)> {
         unsafe {
             if (*self.0).type_ != uacpi_sys::UACPI_OBJECT_PACKAGE as u8 {
                 None
             } else {
                 let pkg = (*self.0).__bindgen_anon_1.package;
-                Some(slice::from_raw_parts(
-                    (*pkg).objects,
-                    (*pkg).count,
-                ).iter().map(|obj| Self(*obj)))
+                Some(((*pkg).objects, (*pkg).count))
+            }
+        }
+    }
+
+    fn from_element(obj: *mut uacpi_sys::uacpi_object) -> Self {
+        // the package keeps its own reference, every returned element owns a new one
+        unsafe { uacpi_sys::uacpi_object_ref(obj) };
+        Self(obj)
+    }
+
+    /// Resolves a name string to the namespace node it refers to, following the AML
+    /// search rules relative to `scope`. uACPI stores references to named objects inside
+    /// packages, e.g. the elements of _DEP or _PRx, as such strings.
+    /// Returns TypeMismatch if the object is not a string.
+    pub fn resolve_as_node(&self, scope: &NamespaceNode) -> Result<NamespaceNode, Status> {
+        let mut ret = core::ptr::null_mut();
+        let status: Status = unsafe {
+            uacpi_sys::uacpi_object_resolve_as_aml_namepath(self.0, scope.0, &mut ret).into()
+        };
+
+        match status {
+            Status::Ok => Ok(NamespaceNode(ret)),
+            _ => Err(status)
+        }
+    }
+
+    /// Resolves a reference, e.g. the result of RefOf, to the node of the named object
+    /// it refers to. uACPI doesn't record which node a reference was taken from,
+    /// so the whole namespace is searched for the node holding the referenced object.
+    /// Returns TypeMismatch if the object is not a reference, and NotFound if it refers
+    /// to an unnamed object such as a local or an argument.
+    pub fn resolve_reference(&self) -> Result<NamespaceNode, Status> {
+        if self.object_type() != Some(ObjectType::Reference) {
+            return Err(Status::TypeMismatch);
+        }
+
+        let mut target = self.0;
+        unsafe {
+            while (*target).type_ == uacpi_sys::UACPI_OBJECT_REFERENCE as u8 {
+                target = (*target).__bindgen_anon_1.inner_object;
+            }
+        }
+
+        let mut found = None;
+        NamespaceNode::root().for_each_child(|node| {
+            let object = unsafe { uacpi_sys::uacpi_namespace_node_get_object(node.raw()) };
+            if object == target {
+                found = Some(NamespaceNode(node.0));
+                NsIterDecision::Break
+            } else {
+                NsIterDecision::Continue
+            }
+        })?;
+        found.ok_or(Status::NotFound)
+    }
+
+    /// Returns the elements of a package of integers,
+    /// None if this is not a package or any element is not an integer.
+    pub fn package_as_integers(&self) -> Option<Vec<u64>> {
+        self.get_package()?.map(|obj| obj.get_int()).collect()
+    }
+
+    /// Returns copies of the elements of a package of strings,
+    /// None if this is not a package or any element is not a string.
+    pub fn package_as_strings(&self) -> Option<Vec<CString>> {
+        self.get_package()?.map(|obj| obj.get_string().map(CString::from)).collect()
+    }
+
+    /// Returns the type of this object.
+    pub fn object_type(&self) -> Option<ObjectType> {
+        ObjectType::from_raw(unsafe { (*self.0).type_ } as _)
+    }
+}
+
+impl Debug for Object {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.0.is_null() {
+            return write!(f, "Object::None");
+        }
+        if let Some(value) = self.get_int() {
+            return write!(f, "Object::Integer({:#x})", value);
+        }
+        if let Some(bytes) = self.string_bytes() {
+            // Debug must not panic, so a malformed string is printed as its bytes
+            return match CStr::from_bytes_with_nul(bytes) {
+                Ok(string) => write!(f, "Object::String({:?})", string),
+                Err(_) => write!(f, "Object::String({:02x?})", bytes),
+            };
+        }
+        if let Some(buffer) = self.get_buffer() {
+            // short buffers are printed in full, longer ones only summarized
+            return if buffer.len() <= 16 {
+                write!(f, "Object::Buffer({:02x?})", buffer)
+            } else {
+                write!(f, "Object::Buffer([..; {}])", buffer.len())
+            };
+        }
+        match self.object_type() {
+            Some(ObjectType::Package) => {
+                let len = unsafe { (*(*self.0).__bindgen_anon_1.package).count };
+                write!(f, "Object::Package(len={})", len)
             }
+            Some(typ) => write!(f, "Object::{:?}", typ),
+            None => write!(f, "Object::Unknown({})", unsafe { (*self.0).type_ }),
         }
     }
 }
 
+impl Clone for Object {
+    fn clone(&self) -> Self {
+        unsafe {
+            uacpi_sys::uacpi_object_ref(self.0);
+        }
+        Self(self.0)
+    }
+}
+
 impl Drop for Object {
     fn drop(&mut self) {
         unsafe {
@@ -362,3 +922,44 @@ impl Drop for Object {
         }
     }
 }
+
+enum Arg<'a> {
+    Integer(u64),
+    String(&'a CStr),
+    Buffer(&'a [u8]),
+}
+
+/// Arguments for eval_args, built from plain values.
+/// The objects are only created for the duration of the evaluation.
+#[derive(Default)]
+pub struct Args<'a>(Vec<Arg<'a>>);
+
+impl<'a> Args<'a> {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn integer(mut self, value: u64) -> Self {
+        self.0.push(Arg::Integer(value));
+        self
+    }
+
+    pub fn string(mut self, value: &'a CStr) -> Self {
+        self.0.push(Arg::String(value));
+        self
+    }
+
+    pub fn buffer(mut self, value: &'a [u8]) -> Self {
+        self.0.push(Arg::Buffer(value));
+        self
+    }
+
+    /// Creates the objects for the arguments, in order.
+    pub(crate) fn to_objects(&self) -> Result<Vec<Object>, Status> {
+        self.0.iter().map(|arg| match arg {
+            Arg::Integer(value) => Object::new_int(*value),
+            Arg::String(value) => Object::new_string(value),
+            Arg::Buffer(value) => Object::new_buffer(value),
+        }.ok_or(Status::OutOfMemory)).collect()
+    }
+}
@@ -1,5 +1,6 @@
 use core::ffi::{c_void, CStr};
-use crate::{NamespaceNode, Status};
+use crate::namespace::iteration_cb;
+use crate::{eval, eval_simple_integer, eval_simple_package, require_init_level, AcpiName, InitLevel, NamespaceNode, Object, ObjectType, Status};
 
 extern crate alloc;
 
@@ -45,6 +46,98 @@ impl IdString {
 	}
 }
 
+/// Returns the contents of an id string without the null terminator.
+unsafe fn id_string_as_str(id: &uacpi_sys::uacpi_id_string) -> &str {
+	if id.value.is_null() || id.size == 0 {
+		return "";
+	}
+	let slice = slice::from_raw_parts(id.value as *const u8, id.size as usize);
+	CStr::from_bytes_with_nul(slice)
+		.ok()
+		.and_then(|str| str.to_str().ok())
+		.unwrap_or("")
+}
+
+/// Information about a namespace node collected by uACPI in one go,
+/// see NamespaceNode::info.
+pub struct NodeInfo(pub(crate) *mut uacpi_sys::uacpi_namespace_node_info);
+
+impl Drop for NodeInfo {
+	fn drop(&mut self) {
+		unsafe {
+			uacpi_sys::uacpi_free_namespace_node_info(self.0);
+		}
+	}
+}
+
+impl NodeInfo {
+	fn info(&self) -> &uacpi_sys::uacpi_namespace_node_info {
+		unsafe { &*self.0 }
+	}
+
+	fn has(&self, flag: u32) -> bool {
+		u32::from(self.info().flags) & flag != 0
+	}
+
+	pub fn name(&self) -> AcpiName {
+		AcpiName(unsafe { self.info().name.id }.to_le_bytes())
+	}
+
+	/// Returns the type of the object attached to the node.
+	pub fn object_type(&self) -> Option<ObjectType> {
+		ObjectType::from_raw(self.info().type_)
+	}
+
+	/// Returns the number of arguments if the node is a method, 0 otherwise.
+	pub fn num_params(&self) -> u8 {
+		self.info().num_params
+	}
+
+	/// Returns the value of _ADR.
+	pub fn adr(&self) -> Option<u64> {
+		self.has(uacpi_sys::UACPI_NS_NODE_INFO_HAS_ADR).then(|| self.info().adr)
+	}
+
+	/// Returns the value of _HID.
+	pub fn hid(&self) -> Option<&str> {
+		self.has(uacpi_sys::UACPI_NS_NODE_INFO_HAS_HID)
+			.then(|| unsafe { id_string_as_str(&self.info().hid) })
+	}
+
+	/// Returns the value of _UID.
+	pub fn uid(&self) -> Option<&str> {
+		self.has(uacpi_sys::UACPI_NS_NODE_INFO_HAS_UID)
+			.then(|| unsafe { id_string_as_str(&self.info().uid) })
+	}
+
+	/// Returns the value of _CLS as a string.
+	pub fn cls(&self) -> Option<&str> {
+		self.has(uacpi_sys::UACPI_NS_NODE_INFO_HAS_CLS)
+			.then(|| unsafe { id_string_as_str(&self.info().cls) })
+	}
+
+	/// Returns the values of _CID, empty if the node doesn't have one.
+	pub fn cid(&self) -> impl Iterator<Item=&str> {
+		let ids = if self.has(uacpi_sys::UACPI_NS_NODE_INFO_HAS_CID) {
+			let cid = &self.info().cid;
+			unsafe { cid.ids.as_slice(cid.num_ids as usize) }
+		} else {
+			&[]
+		};
+		ids.iter().map(|id| unsafe { id_string_as_str(id) })
+	}
+
+	/// Returns the values of _S1D to _S4D, entries without a method are 0xFF.
+	pub fn sxd(&self) -> Option<[u8; 4]> {
+		self.has(uacpi_sys::UACPI_NS_NODE_INFO_HAS_SXD).then(|| self.info().sxd)
+	}
+
+	/// Returns the values of _S0W to _S4W, entries without a method are 0xFF.
+	pub fn sxw(&self) -> Option<[u8; 5]> {
+		self.has(uacpi_sys::UACPI_NS_NODE_INFO_HAS_SXW).then(|| self.info().sxw)
+	}
+}
+
 /// Checks whether the device at `node` matches any of the PNP ids provided in `list`.
 /// This is done by first attempting to match the value returned from _HID
 /// and then the value(s) from _CID.
@@ -57,21 +150,15 @@ pub fn device_matches_pnp_id(node: &NamespaceNode, list: &[&CStr]) -> bool {
 	}
 }
 
-unsafe extern "C" fn uacpi_iter_cb<F: FnMut(&NamespaceNode) -> NsIterDecision>(
-	user: *mut c_void,
-	node: *mut uacpi_sys::uacpi_namespace_node
-) -> uacpi_sys::uacpi_ns_iteration_decision {
-	let f = user as *mut F;
-	(*f)(*(node as *mut _)) as _
-}
-
 /// Finds all the devices in the namespace starting at `parent` matching the
 /// specified `hids`. Only devices reported as present via _STA are checked.
 /// Any matching devices are then passed to the `cb`.
+/// uACPI skips devices whose _STA or _HID evaluation fails instead of failing the walk,
+/// so an error is never specific to a device, see try_find_devices_at for errors that are.
 pub fn find_devices_at<F: FnMut(&NamespaceNode) -> NsIterDecision>(
 	parent: &NamespaceNode,
 	hids: &[&CStr],
-	cb: F
+	mut cb: F
 ) -> Result<(), Status> {
 	let mut vec: Vec<_> = hids.iter().map(|str| str.as_ptr()).collect();
 	vec.push(core::ptr::null());
@@ -79,8 +166,8 @@ pub fn find_devices_at<F: FnMut(&NamespaceNode) -> NsIterDecision>(
 		uacpi_sys::uacpi_find_devices_at(
 			parent.0,
 			vec.as_ptr(),
-			Some(uacpi_iter_cb::<F>),
-			&cb as *const _ as _
+			Some(iteration_cb::<F>),
+			&mut cb as *mut F as *mut c_void
 		).into()
 	};
 
@@ -94,13 +181,13 @@ pub fn find_devices_at<F: FnMut(&NamespaceNode) -> NsIterDecision>(
 /// matches one hid.
 pub fn find_devices<F: FnMut(&NamespaceNode) -> NsIterDecision>(
 	hid: &CStr,
-	cb: F
+	mut cb: F
 ) -> Result<(), Status> {
 	let status: Status = unsafe {
 		uacpi_sys::uacpi_find_devices(
 			hid.as_ptr(),
-			Some(uacpi_iter_cb::<F>),
-			&cb as *const _ as _
+			Some(iteration_cb::<F>),
+			&mut cb as *mut F as *mut c_void
 		).into()
 	};
 
@@ -110,8 +197,83 @@ pub fn find_devices<F: FnMut(&NamespaceNode) -> NsIterDecision>(
 	}
 }
 
-/// Sets the currently active interrupt model.
+/// The error returned by try_find_devices_at and try_find_devices.
+#[derive(Debug)]
+pub struct FindDevicesError {
+	/// The error that stopped the walk.
+	pub status: Status,
+	/// The device `cb` failed on, None if the walk itself failed.
+	pub node: Option<NamespaceNode>,
+}
+
+impl FindDevicesError {
+	fn walk(status: Status) -> Self {
+		Self { status, node: None }
+	}
+}
+
+/// Wraps a fallible `cb` so the node it failed on is stored in `result` and the walk stops.
+fn record_failure<'a, F: FnMut(&NamespaceNode) -> Result<NsIterDecision, Status> + 'a>(
+	result: &'a mut Result<(), FindDevicesError>,
+	mut cb: F
+) -> impl FnMut(&NamespaceNode) -> NsIterDecision + 'a {
+	move |node| match cb(node) {
+		Ok(decision) => decision,
+		Err(status) => {
+			*result = Err(FindDevicesError { status, node: Some(NamespaceNode(node.0)) });
+			NsIterDecision::Break
+		}
+	}
+}
+
+/// Same as find_devices_at, except `cb` can fail.
+/// Returning an error from `cb` stops the walk and the error is returned from this,
+/// along with the node `cb` failed on.
+pub fn try_find_devices_at<F: FnMut(&NamespaceNode) -> Result<NsIterDecision, Status>>(
+	parent: &NamespaceNode,
+	hids: &[&CStr],
+	cb: F
+) -> Result<(), FindDevicesError> {
+	let mut result = Ok(());
+	find_devices_at(parent, hids, record_failure(&mut result, cb))
+		.map_err(FindDevicesError::walk)?;
+	result
+}
+
+/// Same as find_devices, except `cb` can fail.
+/// Returning an error from `cb` stops the walk and the error is returned from this,
+/// along with the node `cb` failed on.
+pub fn try_find_devices<F: FnMut(&NamespaceNode) -> Result<NsIterDecision, Status>>(
+	hid: &CStr,
+	cb: F
+) -> Result<(), FindDevicesError> {
+	let mut result = Ok(());
+	find_devices(hid, record_failure(&mut result, cb))
+		.map_err(FindDevicesError::walk)?;
+	result
+}
+
+/// Collects all the devices in the namespace matching any of the specified `hids`.
+/// Only devices reported as present via _STA are returned.
+/// The nodes are owned by the uACPI namespace and stay valid until uacpi_state_reset,
+/// or until the table that defined them is unloaded, see NamespaceNode::is_dangling.
+pub fn collect_devices(hids: &[&CStr]) -> Result<Vec<NamespaceNode>, Status> {
+	let mut devices = Vec::new();
+	let root = NamespaceNode(unsafe { uacpi_sys::uacpi_namespace_root() });
+	find_devices_at(&root, hids, |node| {
+		devices.push(NamespaceNode(node.0));
+		NsIterDecision::Continue
+	})?;
+	Ok(devices)
+}
+
+/// Sets the currently active interrupt model, which is passed to _PIC.
+/// Must be called after namespace_load and before namespace_initialize and
+/// finalize_gpe_initialization, see the latter for the whole boot sequence.
+/// Returns InitLevelMismatch if the namespace is not loaded yet.
 pub fn set_interrupt_model(model: InterruptModel) -> Result<(), Status> {
+	require_init_level(InitLevel::NamespaceLoaded)?;
+
 	let status: Status = unsafe {
 		uacpi_sys::uacpi_set_interrupt_model(
 			model as _
@@ -139,3 +301,240 @@ pub fn eval_hid(node: &NamespaceNode) -> Result<IdString, Status> {
 		_ => Err(status)
 	}
 }
+
+/// _STA bit: the device is present.
+pub const STA_PRESENT: u32 = 1 << 0;
+/// _STA bit: the device is enabled and decoding its resources.
+pub const STA_ENABLED: u32 = 1 << 1;
+/// _STA bit: the device should be shown in the UI.
+pub const STA_SHOWN_IN_UI: u32 = 1 << 2;
+/// _STA bit: the device is functioning properly.
+pub const STA_FUNCTIONING: u32 = 1 << 3;
+/// _STA bit: a battery is present.
+pub const STA_BATTERY_PRESENT: u32 = 1 << 4;
+
+/// Evaluate a device's _STA method and get its value.
+/// If the device doesn't have a _STA, it's assumed to be present, enabled and functioning.
+pub fn eval_sta(node: &NamespaceNode) -> Result<u32, Status> {
+	let mut flags = 0;
+	let status: Status = unsafe {
+		uacpi_sys::uacpi_eval_sta(
+			node.0,
+			&mut flags
+		).into()
+	};
+
+	match status {
+		Status::Ok => Ok(flags),
+		_ => Err(status)
+	}
+}
+
+/// Evaluate a device's _PXM method and get the proximity domain it belongs to,
+/// the same domains as in the SRAT.
+/// Devices without a _PXM inherit the domain of their closest ancestor that has one,
+/// NotFound is returned if no ancestor has one either.
+/// Returns TypeMismatch if the domain doesn't fit into 32 bits.
+pub fn eval_pxm(node: &NamespaceNode) -> Result<u32, Status> {
+	let mut current = Some(NamespaceNode(node.0));
+	while let Some(node) = current {
+		match eval_simple_integer(&node, c"_PXM") {
+			Ok(pxm) => return u32::try_from(pxm).map_err(|_| Status::TypeMismatch),
+			Err(Status::NotFound) => current = node.parent(),
+			Err(status) => return Err(status),
+		}
+	}
+
+	Err(Status::NotFound)
+}
+
+/// Evaluate a device's _CCA method and get whether its DMA is cache coherent.
+/// Devices without a _CCA inherit it from their closest ancestor that has one.
+/// If no ancestor has one either, x86 devices are coherent, while other architectures
+/// such as ARM require _CCA and NotFound is returned, i.e. the device must not do DMA.
+pub fn eval_cca(node: &NamespaceNode) -> Result<bool, Status> {
+	let mut current = Some(NamespaceNode(node.0));
+	while let Some(node) = current {
+		match eval_simple_integer(&node, c"_CCA") {
+			Ok(cca) => return Ok(cca != 0),
+			Err(Status::NotFound) => current = node.parent(),
+			Err(status) => return Err(status),
+		}
+	}
+
+	if cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+		Ok(true)
+	} else {
+		Err(Status::NotFound)
+	}
+}
+
+/// A range of memory a device can access through DMA, as described by _DMA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaRange {
+	/// The address the device uses to access the start of the range.
+	pub bus_address: u64,
+	/// The address of the start of the range as seen by the CPU.
+	pub cpu_address: u64,
+	pub length: u64,
+}
+
+/// Evaluate a device's _DMA method and get the memory ranges its DMA can reach.
+/// Returns NotFound if the device has no _DMA, its DMA then has no restrictions
+/// beyond those of its parent bus. Only the memory ranges of the word, dword, qword
+/// and extended address space descriptors are returned, other descriptors are skipped.
+pub fn eval_dma_ranges(node: &NamespaceNode) -> Result<Vec<DmaRange>, Status> {
+	let obj = eval(node, c"_DMA", [])?;
+	let mut data = obj.get_buffer().ok_or(Status::TypeMismatch)?;
+	let mut ranges = Vec::new();
+
+	while let Some(&tag) = data.first() {
+		if tag & 0x80 == 0 {
+			// small item, the end tag terminates the template
+			if tag >> 3 == 0xF {
+				break;
+			}
+			data = data.get(1 + (tag & 0x7) as usize..).ok_or(Status::AmlInvalidResource)?;
+			continue;
+		}
+
+		let header = data.get(1..3).ok_or(Status::AmlInvalidResource)?;
+		let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+		let body = data.get(3..3 + len).ok_or(Status::AmlInvalidResource)?;
+		data = &data[3 + len..];
+
+		if let Some(range) = decode_address_space(tag, body) {
+			ranges.push(range);
+		}
+	}
+
+	Ok(ranges)
+}
+
+/// Decodes a memory range from the body of an address space descriptor,
+/// None for other descriptors, other resource types or truncated ones.
+fn decode_address_space(tag: u8, body: &[u8]) -> Option<DmaRange> {
+	// the size of each field and where the granularity field starts
+	let (width, start) = match tag {
+		0x88 => (2, 3),
+		0x87 => (4, 3),
+		0x8A => (8, 3),
+		0x8B => (8, 5),
+		_ => return None,
+	};
+	// resource type 0 is a memory range
+	if *body.first()? != 0 {
+		return None;
+	}
+
+	let field = |index: usize| -> Option<u64> {
+		let offset = start + index * width;
+		let bytes = body.get(offset..offset + width)?;
+		Some(bytes.iter().rev().fold(0u64, |value, byte| value << 8 | *byte as u64))
+	};
+	let min = field(1)?;
+	let translation = field(3)?;
+	let length = field(4)?;
+
+	Some(DmaRange {
+		bus_address: min,
+		cpu_address: min.wrapping_add(translation),
+		length,
+	})
+}
+
+/// Evaluate a device's _DEP method and get the devices it depends on, e.g. an EC or a
+/// GPIO controller that must be initialized before it.
+/// Returns NotFound if the device has no _DEP, and the resolution error if any entry
+/// refers to a device that doesn't exist (yet), e.g. one defined by an unloaded SSDT.
+pub fn eval_dep(node: &NamespaceNode) -> Result<Vec<NamespaceNode>, Status> {
+	eval_simple_package(node, c"_DEP")?
+		.iter()
+		.map(|dep| dep.resolve_as_node(node))
+		.collect()
+}
+
+/// _OSC status dword bit: the firmware failed to process the request.
+pub const OSC_FAILURE: u32 = 1 << 1;
+/// _OSC status dword bit: the UUID is not recognized by the firmware.
+pub const OSC_UNRECOGNIZED_UUID: u32 = 1 << 2;
+/// _OSC status dword bit: the revision is not recognized by the firmware.
+pub const OSC_UNRECOGNIZED_REVISION: u32 = 1 << 3;
+/// _OSC status dword bit: some of the requested capabilities were masked off.
+pub const OSC_CAPABILITIES_MASKED: u32 = 1 << 4;
+
+/// Evaluate a device's _OSC method to negotiate OS capabilities.
+/// `uuid` is the buffer form of the UUID, as produced by the ASL ToUUID macro.
+/// `capabilities` holds the capabilities buffer, with the query/status dword at index 0,
+/// and is overwritten with the dwords acknowledged by the firmware.
+/// Returns TypeMismatch, leaving `capabilities` untouched, if the returned buffer is shorter.
+/// The OSC_* bits in the returned status dword must be checked by the caller.
+pub fn eval_osc(
+	node: &NamespaceNode,
+	uuid: &[u8; 16],
+	revision: u64,
+	capabilities: &mut [u32]
+) -> Result<(), Status> {
+	let caps: Vec<u8> = capabilities.iter().flat_map(|dword| dword.to_le_bytes()).collect();
+	let args = [
+		Object::new_buffer(uuid).ok_or(Status::OutOfMemory)?,
+		Object::new_int(revision).ok_or(Status::OutOfMemory)?,
+		Object::new_int(capabilities.len() as u64).ok_or(Status::OutOfMemory)?,
+		Object::new_buffer(&caps).ok_or(Status::OutOfMemory)?,
+	];
+
+	let ret = eval(node, c"_OSC", &args)?;
+	let buffer = ret.get_buffer().ok_or(Status::TypeMismatch)?;
+	if buffer.len() < capabilities.len() * 4 {
+		return Err(Status::TypeMismatch);
+	}
+	for (dword, bytes) in capabilities.iter_mut().zip(buffer.chunks_exact(4)) {
+		*dword = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+	}
+
+	Ok(())
+}
+
+/// Evaluate a device's _DSM method.
+/// `uuid` is the buffer form of the UUID, as produced by the ASL ToUUID macro,
+/// `args` are passed as the package in the fourth argument.
+pub fn eval_dsm(
+	node: &NamespaceNode,
+	uuid: &[u8; 16],
+	revision: u64,
+	function: u64,
+	args: &[Object]
+) -> Result<Object, Status> {
+	let args = [
+		Object::new_buffer(uuid).ok_or(Status::OutOfMemory)?,
+		Object::new_int(revision).ok_or(Status::OutOfMemory)?,
+		Object::new_int(function).ok_or(Status::OutOfMemory)?,
+		Object::new_package(args).ok_or(Status::OutOfMemory)?,
+	];
+
+	eval(node, c"_DSM", &args)
+}
+
+/// Calls function 0 of a device's _DSM and returns the indices of the supported functions.
+/// The list is empty if the firmware doesn't support the UUID or revision at all.
+pub fn dsm_query_supported_functions(
+	node: &NamespaceNode,
+	uuid: &[u8; 16],
+	revision: u64
+) -> Result<Vec<u64>, Status> {
+	let ret = eval_dsm(node, uuid, revision, 0, &[])?;
+	let bitmap = match (ret.get_buffer(), ret.get_int()) {
+		(Some(buffer), _) => buffer.to_vec(),
+		(None, Some(value)) => value.to_le_bytes().to_vec(),
+		(None, None) => return Err(Status::TypeMismatch),
+	};
+
+	// bit 0 clear means that no functions are supported for this UUID and revision
+	if !matches!(bitmap.first(), Some(byte) if byte & 1 != 0) {
+		return Ok(Vec::new());
+	}
+
+	Ok(bitmap.iter().enumerate().flat_map(|(i, byte)| {
+		(0..8).filter(move |bit| byte & (1 << bit) != 0).map(move |bit| (i * 8 + bit) as u64)
+	}).collect())
+}